@@ -0,0 +1,166 @@
+use macroquad::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::{mix_color, BACKGROUND_COLOR, PLATFORM_COLOR, SHADOW_COLOR, STATIC_PLATFORM_COLOR, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// Day/night cycle. Time of day goes from 0.0 to 1.0:
+// 0.0 = sunrise, 0.25 = noon, 0.5 = sunset, 0.75 = midnight
+// How fast it goes is up to the level, see `level::SunConfig`
+
+// Palette keyframes at sunrise, noon, sunset and midnight
+const SKY_TOP: [Color; 4] = [
+    Color::new(0.60, 0.65, 0.85, 1.0),
+    Color::new(0.65, 0.82, 0.95, 1.0),
+    Color::new(0.55, 0.40, 0.60, 1.0),
+    Color::new(0.40, 0.40, 0.60, 1.0),
+];
+const SKY_BOTTOM: [Color; 4] = [
+    Color::new(0.98, 0.78, 0.62, 1.0),
+    BACKGROUND_COLOR,
+    Color::new(0.95, 0.60, 0.40, 1.0),
+    Color::new(0.62, 0.55, 0.62, 1.0),
+];
+const LIGHT: [Color; 4] = [
+    Color::new(0.95, 0.85, 0.85, 1.0),
+    Color::new(1.00, 1.00, 1.00, 1.0),
+    Color::new(0.95, 0.72, 0.62, 1.0),
+    Color::new(0.55, 0.55, 0.70, 1.0),
+];
+
+// Parallax layers: how much each one follows the camera (0.0 = fixed to the sky)
+const MESA_PARALLAX: f32 = 0.1;
+const FAR_DUNES_PARALLAX: f32 = 0.25;
+const NEAR_DUNES_PARALLAX: f32 = 0.5;
+const DUNE_STEP: f32 = 16.0;   // Horizontal resolution of the dune silhouettes
+const SKY_BANDS: usize = 24;   // Strips used to draw the sky gradient
+const STAR_COUNT: usize = 40;
+
+// Height of the sun: 1.0 at noon, 0.0 at sunrise/sunset, -1.0 at midnight
+pub fn sun_height(time_of_day: f32) -> f32 {
+    (time_of_day * TAU).sin()
+}
+
+fn sample(keys: &[Color; 4], time_of_day: f32) -> Color {
+    let position = time_of_day.rem_euclid(1.0) * 4.0;
+    let index = position.floor() as usize % 4;
+    mix_color(keys[index], keys[(index + 1) % 4], position.fract())
+}
+
+pub fn tinted(color: Color, light: Color) -> Color {
+    Color::new(color.r * light.r, color.g * light.g, color.b * light.b, color.a)
+}
+
+pub struct Palette {
+    pub sky_top: Color,
+    pub sky_bottom: Color,
+    pub light: Color,
+}
+
+impl Palette {
+    pub fn at(time_of_day: f32) -> Self {
+        Self {
+            sky_top: sample(&SKY_TOP, time_of_day),
+            sky_bottom: sample(&SKY_BOTTOM, time_of_day),
+            light: sample(&LIGHT, time_of_day),
+        }
+    }
+}
+
+// `camera_x` is how far the view has scrolled (or the player's offset on a static screen)
+pub fn draw_background(time_of_day: f32, camera_x: f32) {
+    let palette = Palette::at(time_of_day);
+
+    draw_sky(&palette);
+    draw_stars(time_of_day);
+    draw_sun_and_moon(time_of_day);
+
+    let mesa_color = tinted(mix_color(PLATFORM_COLOR, SHADOW_COLOR, 0.35), palette.light);
+    let far_color = tinted(mix_color(STATIC_PLATFORM_COLOR, palette.sky_bottom, 0.4), palette.light);
+    let near_color = tinted(PLATFORM_COLOR, palette.light);
+
+    draw_mesas(camera_x * MESA_PARALLAX, mesa_color);
+    draw_dunes(camera_x * FAR_DUNES_PARALLAX, WINDOW_HEIGHT * 0.72, 18.0, 0.011, far_color);
+    draw_dunes(camera_x * NEAR_DUNES_PARALLAX, WINDOW_HEIGHT * 0.84, 26.0, 0.007, near_color);
+}
+
+fn draw_sky(palette: &Palette) {
+    let band_height = WINDOW_HEIGHT / SKY_BANDS as f32;
+    for i in 0..SKY_BANDS {
+        let t = i as f32 / (SKY_BANDS - 1) as f32;
+        let color = mix_color(palette.sky_top, palette.sky_bottom, t);
+        draw_rectangle(0.0, i as f32 * band_height, WINDOW_WIDTH, band_height + 1.0, color);
+    }
+}
+
+fn draw_stars(time_of_day: f32) {
+    let night = (-sun_height(time_of_day)).clamp(0.0, 1.0);
+    if night <= 0.0 {
+        return;
+    }
+
+    // Fixed pseudo-random positions, so they don't jump around between frames
+    for i in 0..STAR_COUNT {
+        let seed = i as f32 * 12.9898;
+        let x = (seed.sin() * 43758.545).fract().abs() * WINDOW_WIDTH;
+        let y = ((seed * 1.7).cos() * 24634.633).fract().abs() * WINDOW_HEIGHT * 0.55;
+        draw_rectangle(x, y, 2.0, 2.0, Color::new(1.0, 0.97, 0.9, night * 0.8));
+    }
+}
+
+fn draw_sun_and_moon(time_of_day: f32) {
+    // Both travel along the same arc, half a day apart
+    let arc = |t: f32| {
+        let angle = t * TAU;
+        vec2(
+            WINDOW_WIDTH * 0.5 - angle.cos() * WINDOW_WIDTH * 0.45,
+            WINDOW_HEIGHT * 0.75 - angle.sin() * WINDOW_HEIGHT * 0.6,
+        )
+    };
+
+    let sun = arc(time_of_day);
+    draw_circle(sun.x, sun.y, 28.0, Color::new(1.0, 0.92, 0.6, 0.9));
+    let moon = arc(time_of_day + 0.5);
+    draw_circle(moon.x, moon.y, 18.0, Color::new(0.95, 0.95, 1.0, 0.8));
+}
+
+fn draw_mesas(offset: f32, color: Color) {
+    // (center, top width, height) of each mesa, repeated every screen width
+    let mesas = [(120.0, 90.0, 120.0), (430.0, 140.0, 90.0), (690.0, 70.0, 150.0)];
+    let base_y = WINDOW_HEIGHT * 0.78;
+
+    for (center, width, height) in mesas {
+        let x = (center - offset).rem_euclid(WINDOW_WIDTH + 200.0) - 100.0;
+        let top = base_y - height;
+        let slope = height * 0.4;
+
+        draw_rectangle(x - width * 0.5, top, width, height, color);
+        draw_triangle(
+            vec2(x - width * 0.5, top),
+            vec2(x - width * 0.5, base_y),
+            vec2(x - width * 0.5 - slope, base_y),
+            color,
+        );
+        draw_triangle(
+            vec2(x + width * 0.5, top),
+            vec2(x + width * 0.5, base_y),
+            vec2(x + width * 0.5 + slope, base_y),
+            color,
+        );
+    }
+}
+
+fn draw_dunes(offset: f32, base_y: f32, amplitude: f32, frequency: f32, color: Color) {
+    let height_at = |x: f32| {
+        let world_x = x + offset;
+        base_y - amplitude * ((world_x * frequency).sin() + 0.5 * (world_x * frequency * 2.3).sin())
+    };
+
+    let mut x = 0.0;
+    while x < WINDOW_WIDTH {
+        let left = vec2(x, height_at(x));
+        let right = vec2(x + DUNE_STEP, height_at(x + DUNE_STEP));
+        draw_triangle(left, right, vec2(x, WINDOW_HEIGHT), color);
+        draw_triangle(right, vec2(x + DUNE_STEP, WINDOW_HEIGHT), vec2(x, WINDOW_HEIGHT), color);
+        x += DUNE_STEP;
+    }
+}
@@ -0,0 +1,28 @@
+use macroquad::prelude::*;
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// Where the view sits in the world. Classic mode never moves it,
+// the endless modes scroll it along as the player makes progress
+const CLIMB_FOLLOW_LINE: f32 = 0.4;  // Climbing above this fraction of the screen pulls the view up
+
+#[derive(Clone, Copy)]
+pub struct GameCamera {
+    pub position: Vec2,  // Top left corner of the view, in world space
+}
+
+impl GameCamera {
+    pub fn new() -> Self {
+        Self { position: Vec2::ZERO }
+    }
+
+    // Creep upwards, faster if the player climbs ahead. Never scrolls back down
+    pub fn climb(&mut self, player_y: f32, scroll_speed: f32, dt: f32) {
+        let follow = player_y - WINDOW_HEIGHT * CLIMB_FOLLOW_LINE;
+        self.position.y = (self.position.y - scroll_speed * dt).min(follow);
+    }
+
+    pub fn view(&self) -> Rect {
+        Rect::new(self.position.x, self.position.y, WINDOW_WIDTH, WINDOW_HEIGHT)
+    }
+}
@@ -0,0 +1,93 @@
+use macroquad::prelude::*;
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// Everything is drawn at a fixed virtual resolution, then scaled to the window
+const LETTERBOX_COLOR: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScaleMode {
+    Fit,     // Scale as large as possible while keeping the aspect ratio
+    Integer, // Only whole-number scales, for crisp pixels
+}
+
+pub struct Canvas {
+    target: RenderTarget,
+    camera: Camera2D,
+    pub scale_mode: ScaleMode,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        let target = render_target(WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32);
+        target.texture.set_filter(FilterMode::Nearest);
+
+        let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT));
+        camera.render_target = Some(target.clone());
+
+        Self {
+            target,
+            camera,
+            scale_mode: ScaleMode::Fit,
+        }
+    }
+
+    // Start drawing in game space
+    pub fn begin(&mut self) {
+        self.set_view(Vec2::ZERO);
+    }
+
+    // Show the part of the world whose top left corner is at `position`,
+    // used for scrolling and screen shake. The HUD is drawn at Vec2::ZERO
+    pub fn set_view(&mut self, position: Vec2) {
+        self.camera.target = vec2(WINDOW_WIDTH, WINDOW_HEIGHT) * 0.5 + position;
+        set_camera(&self.camera);
+    }
+
+    // Draw the finished frame to the window, centered with black bars
+    pub fn present(&self) {
+        set_default_camera();
+        clear_background(LETTERBOX_COLOR);
+
+        let scale = self.scale();
+        let offset = self.offset(scale);
+        draw_texture_ex(
+            &self.target.texture,
+            offset.x,
+            offset.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(WINDOW_WIDTH * scale, WINDOW_HEIGHT * scale)),
+                flip_y: true, // Render targets come out upside down
+                ..Default::default()
+            },
+        );
+    }
+
+    fn scale(&self) -> f32 {
+        let fit = (screen_width() / WINDOW_WIDTH).min(screen_height() / WINDOW_HEIGHT);
+        match self.scale_mode {
+            ScaleMode::Fit => fit,
+            // Windows smaller than the canvas still have to fit somehow
+            ScaleMode::Integer if fit >= 1.0 => fit.floor(),
+            ScaleMode::Integer => fit,
+        }
+    }
+
+    fn offset(&self, scale: f32) -> Vec2 {
+        vec2(
+            (screen_width() - WINDOW_WIDTH * scale) * 0.5,
+            (screen_height() - WINDOW_HEIGHT * scale) * 0.5,
+        )
+    }
+
+    // Map a window position back into game space
+    pub fn to_game(&self, screen_pos: Vec2) -> Vec2 {
+        let scale = self.scale();
+        (screen_pos - self.offset(scale)) / scale
+    }
+
+    pub fn mouse_position(&self) -> Vec2 {
+        self.to_game(mouse_position().into())
+    }
+}
@@ -0,0 +1,416 @@
+use macroquad::prelude::*;
+use std::time::SystemTime;
+
+use crate::rng::hash_text;
+
+// Where designers tune the game. If the file can't be read (e.g. on the web),
+// the copy embedded at build time is used instead.
+const CONFIG_PATH: &str = "assets/config.txt";
+const EMBEDDED_CONFIG: &str = include_str!("../assets/config.txt");
+const RELOAD_CHECK_INTERVAL: f32 = 1.0; // Seconds between checks for changes on disk
+
+// Default values, used for anything the config file doesn't set
+const GRAVITY: f32 = 500.0;
+const FALL_GRAVITY: f32 = 800.0;  // Falling is snappier than rising
+const PLAYER_SPEED: f32 = 150.0;
+const JUMP_FORCE: f32 = -350.0;
+const PLATFORM_SPEED: f32 = 50.0;
+const INVULNERABILITY_DURATION: f32 = 3.0; // Seconds of invulnerability after getting hit
+const SHADOW_FRAMES_DELAY: usize = 25;
+const INITIAL_LIVES: i32 = 3;
+
+// Movement feel
+const COYOTE_TIME: f32 = 0.1;       // Seconds the player can still jump after walking off a ledge
+const JUMP_BUFFER_TIME: f32 = 0.1;  // Seconds a jump press is remembered before landing
+const JUMP_CUT: f32 = 0.5;          // Upward speed kept when Space is released early
+const GROUND_ACCELERATION: f32 = 1200.0;
+const GROUND_DECELERATION: f32 = 1500.0;
+const AIR_ACCELERATION: f32 = 800.0;
+const AIR_DECELERATION: f32 = 400.0;
+
+// Movement abilities
+const DOUBLE_JUMP_FORCE: f32 = -300.0;
+const WALL_SLIDE_SPEED: f32 = 80.0;    // Max fall speed while sliding down a wall
+const WALL_JUMP_PUSH: f32 = 200.0;     // Horizontal kick away from the wall
+const WALL_JUMP_LOCK: f32 = 0.15;      // Seconds horizontal input is ignored after a wall jump
+const DASH_SPEED: f32 = 450.0;
+const DASH_DURATION: f32 = 0.15;
+const DASH_COOLDOWN: f32 = 0.8;
+
+// Coins
+const COIN_SPAWN_INTERVAL: f32 = 3.0;  // Spawn a new coin every 3 seconds
+const COMMON_COIN: CoinTierConfig = CoinTierConfig { points: 10, lifetime: 5.0, weight: 70 };
+const RARE_COIN: CoinTierConfig = CoinTierConfig { points: 25, lifetime: 4.0, weight: 25 };
+const GOLD_COIN: CoinTierConfig = CoinTierConfig { points: 50, lifetime: 2.0, weight: 5 };  // Gold coins don't stay around for long
+
+// Versus
+const VERSUS_TIME: f32 = 60.0;   // Seconds the runner has to survive
+const VERSUS_SPEED: f32 = 0.8;   // The steered shadow's top and dash speed, relative to the runner's
+const VERSUS_LEASH: f32 = 150.0; // Pixels the steered shadow can stray from the runner's trail
+
+// One `key = value` line of a data file, with the `[section]` it appeared under
+pub struct Entry<'a> {
+    pub section: String,
+    pub key: &'a str,
+    pub value: &'a str,
+    pub line: usize,
+}
+
+// The format shared by the data files: `key = value` lines, `[section]` headers and `#` comments
+pub fn parse_entries(text: &str) -> Vec<Entry<'_>> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line[1..line.len() - 1].trim().to_lowercase();
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) => entries.push(Entry {
+                section: section.clone(),
+                key: key.trim(),
+                value: value.trim(),
+                line: line_number + 1,
+            }),
+            None => warn!("line {}: expected `key = value` ({})", line_number + 1, line),
+        }
+    }
+
+    entries
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PhysicsConfig {
+    pub rise_gravity: f32,
+    pub fall_gravity: f32,
+    pub jump_force: f32,
+    pub jump_cut: f32,
+    pub coyote_time: f32,
+    pub jump_buffer_time: f32,
+    pub max_speed: f32,
+    pub ground_acceleration: f32,
+    pub ground_deceleration: f32,
+    pub air_acceleration: f32,
+    pub air_deceleration: f32,
+    pub double_jump_force: f32,
+    pub wall_slide_speed: f32,
+    pub wall_jump_push: f32,
+    pub wall_jump_lock: f32,
+    pub dash_speed: f32,
+    pub dash_duration: f32,
+    pub dash_cooldown: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            rise_gravity: GRAVITY,
+            fall_gravity: FALL_GRAVITY,
+            jump_force: JUMP_FORCE,
+            jump_cut: JUMP_CUT,
+            coyote_time: COYOTE_TIME,
+            jump_buffer_time: JUMP_BUFFER_TIME,
+            max_speed: PLAYER_SPEED,
+            ground_acceleration: GROUND_ACCELERATION,
+            ground_deceleration: GROUND_DECELERATION,
+            air_acceleration: AIR_ACCELERATION,
+            air_deceleration: AIR_DECELERATION,
+            double_jump_force: DOUBLE_JUMP_FORCE,
+            wall_slide_speed: WALL_SLIDE_SPEED,
+            wall_jump_push: WALL_JUMP_PUSH,
+            wall_jump_lock: WALL_JUMP_LOCK,
+            dash_speed: DASH_SPEED,
+            dash_duration: DASH_DURATION,
+            dash_cooldown: DASH_COOLDOWN,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CoinTierConfig {
+    pub points: i32,
+    pub lifetime: f32,
+    pub weight: u32,  // Relative spawn chance
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GameConfig {
+    pub physics: PhysicsConfig,
+    pub shadow_delay: usize,  // Frames the shadow lags behind the player
+    pub lives: i32,
+    pub platform_speed: f32,
+    pub invulnerability_duration: f32,
+    pub coin_spawn_interval: f32,
+    pub common_coin: CoinTierConfig,
+    pub rare_coin: CoinTierConfig,
+    pub gold_coin: CoinTierConfig,
+    pub versus_time: f32,
+    pub versus_speed: f32,
+    pub versus_leash: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            physics: PhysicsConfig::default(),
+            shadow_delay: SHADOW_FRAMES_DELAY,
+            lives: INITIAL_LIVES,
+            platform_speed: PLATFORM_SPEED,
+            invulnerability_duration: INVULNERABILITY_DURATION,
+            coin_spawn_interval: COIN_SPAWN_INTERVAL,
+            common_coin: COMMON_COIN,
+            rare_coin: RARE_COIN,
+            gold_coin: GOLD_COIN,
+            versus_time: VERSUS_TIME,
+            versus_speed: VERSUS_SPEED,
+            versus_leash: VERSUS_LEASH,
+        }
+    }
+}
+
+impl GameConfig {
+    // Changes whenever any setting does, so a replay can tell it's played with the tuning it was recorded with
+    pub fn fingerprint(&self) -> u64 {
+        hash_text(&format!("{:?}", self))
+    }
+
+    pub fn from_text(text: &str, preset: Preset) -> Self {
+        let (config, errors) = Self::parse(text, preset);
+        for error in errors {
+            warn!("config {}", error);
+        }
+        config
+    }
+
+    // Defaults, then the top of the config file, then the preset's section. Also every line that couldn't be used
+    pub fn parse(text: &str, preset: Preset) -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        for entry in parse_entries(text) {
+            if !entry.section.is_empty() && entry.section != preset.section() {
+                continue;
+            }
+
+            let parsed = entry
+                .value
+                .parse::<f32>()
+                .map_err(|e| e.to_string())
+                .and_then(|value| config.set(entry.key, value));
+            if let Err(e) = parsed {
+                errors.push(format!("line {}: {}", entry.line, e));
+            }
+        }
+
+        (config, errors)
+    }
+
+    fn set(&mut self, key: &str, value: f32) -> Result<(), String> {
+        let physics = &mut self.physics;
+        match key {
+            "gravity" => physics.rise_gravity = value,
+            "fall_gravity" => physics.fall_gravity = value,
+            "jump_force" => physics.jump_force = value,
+            "jump_cut" => physics.jump_cut = value,
+            "coyote_time" => physics.coyote_time = value,
+            "jump_buffer_time" => physics.jump_buffer_time = value,
+            "player_speed" => physics.max_speed = value,
+            "ground_acceleration" => physics.ground_acceleration = value,
+            "ground_deceleration" => physics.ground_deceleration = value,
+            "air_acceleration" => physics.air_acceleration = value,
+            "air_deceleration" => physics.air_deceleration = value,
+            "double_jump_force" => physics.double_jump_force = value,
+            "wall_slide_speed" => physics.wall_slide_speed = value,
+            "wall_jump_push" => physics.wall_jump_push = value,
+            "wall_jump_lock" => physics.wall_jump_lock = value,
+            "dash_speed" => physics.dash_speed = value,
+            "dash_duration" => physics.dash_duration = value,
+            "dash_cooldown" => physics.dash_cooldown = value,
+            "shadow_delay" => self.shadow_delay = value.max(1.0) as usize,
+            "lives" => self.lives = value.max(1.0) as i32,
+            "platform_speed" => self.platform_speed = value,
+            "invulnerability_duration" => self.invulnerability_duration = value,
+            "coin_spawn_interval" => self.coin_spawn_interval = value,
+            "versus_time" => self.versus_time = value.max(1.0),
+            "versus_speed" => self.versus_speed = value.max(0.1),
+            "versus_leash" => self.versus_leash = value.max(0.0),
+            _ => {
+                // Coin tiers use `<tier>_coin.<field>` keys
+                let (tier, field) = key.split_once('.').ok_or(format!("unknown key `{}`", key))?;
+                let coin = match tier {
+                    "common_coin" => &mut self.common_coin,
+                    "rare_coin" => &mut self.rare_coin,
+                    "gold_coin" => &mut self.gold_coin,
+                    _ => return Err(format!("unknown key `{}`", key)),
+                };
+                match field {
+                    "points" => coin.points = value as i32,
+                    "lifetime" => coin.lifetime = value,
+                    "weight" => coin.weight = value.max(0.0) as u32,
+                    _ => return Err(format!("unknown key `{}`", key)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Preset {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Easy, Preset::Normal, Preset::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Easy => "Easy",
+            Preset::Normal => "Normal",
+            Preset::Hard => "Hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+    }
+
+    fn section(&self) -> &'static str {
+        match self {
+            Preset::Easy => "easy",
+            Preset::Normal => "normal",
+            Preset::Hard => "hard",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Preset::Easy => Preset::Normal,
+            Preset::Normal => Preset::Hard,
+            Preset::Hard => Preset::Easy,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        match self {
+            Preset::Easy => Preset::Hard,
+            Preset::Normal => Preset::Easy,
+            Preset::Hard => Preset::Normal,
+        }
+    }
+}
+
+// Loads the config file and notices when it changes on disk
+pub struct ConfigFile {
+    last_modified: Option<SystemTime>,
+    check_timer: f32,
+}
+
+impl ConfigFile {
+    pub fn new() -> Self {
+        Self {
+            last_modified: Self::modified(),
+            check_timer: RELOAD_CHECK_INTERVAL,
+        }
+    }
+
+    pub fn load(&self, preset: Preset) -> GameConfig {
+        match std::fs::read_to_string(CONFIG_PATH) {
+            Ok(text) => GameConfig::from_text(&text, preset),
+            Err(_) => GameConfig::from_text(EMBEDDED_CONFIG, preset),
+        }
+    }
+
+    // The config the game was built with, for runs that have to play the same everywhere
+    pub fn embedded(preset: Preset) -> GameConfig {
+        GameConfig::from_text(EMBEDDED_CONFIG, preset)
+    }
+
+    // Returns true when the file changed since the last load
+    pub fn poll(&mut self) -> bool {
+        self.check_timer -= get_frame_time();
+        if self.check_timer > 0.0 {
+            return false;
+        }
+        self.check_timer = RELOAD_CHECK_INTERVAL;
+
+        let modified = Self::modified();
+        if modified.is_some() && modified != self.last_modified {
+            self.last_modified = modified;
+            info!("{} changed, reloading", CONFIG_PATH);
+            return true;
+        }
+        false
+    }
+
+    fn modified() -> Option<SystemTime> {
+        std::fs::metadata(CONFIG_PATH).and_then(|m| m.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_skip_comments_and_bad_lines() {
+        let text = "# tuning\nlives = 3  # per run\n\nnot a setting\n[ Hard ]\nlives = 1\n";
+        let entries = parse_entries(text);
+        let read: Vec<(&str, &str, &str, usize)> = entries
+            .iter()
+            .map(|entry| (entry.section.as_str(), entry.key, entry.value, entry.line))
+            .collect();
+        assert_eq!(read, vec![("", "lives", "3", 2), ("hard", "lives", "1", 6)]);
+    }
+
+    #[test]
+    fn preset_overrides_the_defaults() {
+        let text = "lives = 4\nplatform_speed = 120\n[hard]\nlives = 2\n[easy]\nlives = 6\n";
+        let (hard, errors) = GameConfig::parse(text, Preset::Hard);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(hard.lives, 2);
+        assert_eq!(hard.platform_speed, 120.0);
+        assert_eq!(hard.shadow_delay, GameConfig::default().shadow_delay);
+
+        let (normal, _) = GameConfig::parse(text, Preset::Normal);
+        assert_eq!(normal.lives, 4);
+    }
+
+    #[test]
+    fn unknown_keys_report_their_line() {
+        let (_, errors) = GameConfig::parse("lives = 3\n\nlifes = 3\nrare_coin.colour = 1\nlives = many\n", Preset::Normal);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].starts_with("line 3: unknown key `lifes`"), "{:?}", errors);
+        assert!(errors[1].starts_with("line 4: unknown key `rare_coin.colour`"), "{:?}", errors);
+        assert!(errors[2].starts_with("line 5:"), "{:?}", errors);
+    }
+
+    #[test]
+    fn fingerprint_follows_the_settings() {
+        let config = GameConfig::default();
+        assert_eq!(config.fingerprint(), GameConfig::default().fingerprint());
+
+        let mut tuned = config;
+        tuned.physics.jump_force += 1.0;
+        assert_ne!(config.fingerprint(), tuned.fingerprint());
+    }
+
+    #[test]
+    fn embedded_config_is_clean() {
+        for preset in Preset::ALL {
+            let (_, errors) = GameConfig::parse(EMBEDDED_CONFIG, preset);
+            assert!(errors.is_empty(), "{}: {:?}", preset.name(), errors);
+        }
+    }
+}
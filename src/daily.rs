@@ -0,0 +1,275 @@
+use macroquad::prelude::*;
+
+use crate::config::GameConfig;
+use crate::level::SunConfig;
+use crate::rng::SeededRng;
+use crate::{TEXT_ACCENT, TEXT_PRIMARY, TEXT_SECONDARY};
+
+// The daily challenge: one seed per day, the same for everyone, so results can be compared
+const DAILY_PATH: &str = "daily.txt";
+const MUTATORS_PER_DAY: usize = 2;
+const CALENDAR_CELL: f32 = 24.0;
+const PLAYED_DAY_COLOR: Color = Color::new(0.85, 0.6, 0.2, 0.5);
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+// A day in the UTC calendar, stored as days since 1970-01-01
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    days: i64,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        Self { days: (macroquad::miniquad::date::now() / 86400.0).floor() as i64 }
+    }
+
+    fn from_ymd(year: i64, month: i64, day: i64) -> Self {
+        // Howard Hinnant's days_from_civil
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Self { days: era * 146097 + day_of_era - 719468 }
+    }
+
+    pub fn ymd(&self) -> (i64, i64, i64) {
+        // And its inverse, civil_from_days
+        let z = self.days + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z.rem_euclid(146097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        (year_of_era + era * 400 + (month <= 2) as i64, month, day)
+    }
+
+    // 0 for Monday
+    fn weekday(&self) -> i64 {
+        (self.days + 3).rem_euclid(7)
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split('-').map(|part| part.parse::<i64>().ok());
+        let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+        let date = Self::from_ymd(year, month, day);
+        (date.ymd() == (year, month, day)).then_some(date)
+    }
+
+    pub fn name(&self) -> String {
+        let (year, month, day) = self.ymd();
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    pub fn seed(&self) -> u64 {
+        SeededRng::with_stream(self.days as u64, "daily").next_u64()
+    }
+}
+
+// A twist on the usual rules, picked from the day's seed
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mutator {
+    Dusk,           // The run starts at sunset, with long shadows
+    QuickShadow,    // The shadow follows closer
+    GoldRush,       // Coins come twice as often, with more gold among them
+    LowGravity,     // Floatier jumps
+    FastPlatforms,  // Moving platforms go faster
+    Fragile,        // Fewer lives
+}
+
+impl Mutator {
+    const ALL: [Mutator; 6] = [
+        Mutator::Dusk,
+        Mutator::QuickShadow,
+        Mutator::GoldRush,
+        Mutator::LowGravity,
+        Mutator::FastPlatforms,
+        Mutator::Fragile,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutator::Dusk => "Dusk",
+            Mutator::QuickShadow => "Quick shadow",
+            Mutator::GoldRush => "Gold rush",
+            Mutator::LowGravity => "Low gravity",
+            Mutator::FastPlatforms => "Fast platforms",
+            Mutator::Fragile => "Fragile",
+        }
+    }
+
+    // The day's mutators, all different
+    pub fn for_seed(seed: u64) -> Vec<Mutator> {
+        let mut rng = SeededRng::with_stream(seed, "mutators");
+        let mut left = Self::ALL.to_vec();
+        (0..MUTATORS_PER_DAY).map(|_| left.remove(rng.gen_index(left.len()))).collect()
+    }
+
+    pub fn apply(&self, config: &mut GameConfig, sun: &mut SunConfig) {
+        match self {
+            Mutator::Dusk => sun.start_time = 0.45,
+            Mutator::QuickShadow => config.shadow_delay = (config.shadow_delay * 3 / 4).max(1),
+            Mutator::GoldRush => {
+                config.coin_spawn_interval *= 0.5;
+                config.gold_coin.weight *= 3;
+            }
+            Mutator::LowGravity => {
+                config.physics.rise_gravity *= 0.8;
+                config.physics.fall_gravity *= 0.8;
+            }
+            Mutator::FastPlatforms => config.platform_speed *= 1.5,
+            Mutator::Fragile => config.lives = config.lives.min(2),
+        }
+    }
+}
+
+struct DailyEntry {
+    date: Date,
+    best: f32,
+    attempts: u32,
+}
+
+// Best result and number of attempts for every day played, one `date best attempts` line each
+pub struct DailyLog {
+    entries: Vec<DailyEntry>,
+}
+
+impl DailyLog {
+    pub fn load() -> Self {
+        let text = std::fs::read_to_string(DAILY_PATH).unwrap_or_default();
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let date = Date::parse(parts.next()?)?;
+                let best = parts.next()?.parse().ok()?;
+                let attempts = parts.next()?.parse().ok()?;
+                Some(DailyEntry { date, best, attempts })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    fn save(&self) {
+        let text: String = self
+            .entries
+            .iter()
+            .map(|entry| format!("{} {:.2} {}\n", entry.date.name(), entry.best, entry.attempts))
+            .collect();
+
+        // Not being able to save (e.g. on the web) shouldn't stop the game
+        let _ = std::fs::write(DAILY_PATH, text);
+    }
+
+    fn entry(&self, date: Date) -> Option<&DailyEntry> {
+        self.entries.iter().find(|entry| entry.date == date)
+    }
+
+    pub fn best(&self, date: Date) -> Option<f32> {
+        self.entry(date).map(|entry| entry.best)
+    }
+
+    pub fn attempts(&self, date: Date) -> u32 {
+        self.entry(date).map_or(0, |entry| entry.attempts)
+    }
+
+    // Best of all days
+    pub fn best_ever(&self) -> f32 {
+        self.entries.iter().map(|entry| entry.best).fold(0.0, f32::max)
+    }
+
+    // Returns true if this is a new best for that day
+    pub fn record(&mut self, date: Date, score: f32) -> bool {
+        let is_best = match self.entries.iter_mut().find(|entry| entry.date == date) {
+            Some(entry) => {
+                entry.attempts += 1;
+                let is_best = score > entry.best;
+                entry.best = entry.best.max(score);
+                is_best
+            }
+            None => {
+                self.entries.push(DailyEntry { date, best: score, attempts: 1 });
+                self.entries.sort_by_key(|entry| entry.date);
+                true
+            }
+        };
+        self.save();
+        is_best
+    }
+
+    // The month around `today`: days played are filled in, today is outlined
+    pub fn draw_calendar(&self, today: Date, x: f32, y: f32) {
+        let (year, month, _) = today.ymd();
+        let title = format!("{} {}", MONTH_NAMES[month as usize - 1], year);
+        draw_text(&title, x, y, 20.0, TEXT_ACCENT);
+
+        for (i, weekday) in ["M", "T", "W", "T", "F", "S", "S"].iter().enumerate() {
+            draw_text(weekday, x + i as f32 * CALENDAR_CELL + 7.0, y + 22.0, 16.0, TEXT_SECONDARY);
+        }
+
+        let first = Date::from_ymd(year, month, 1);
+        let mut date = first;
+        while date.ymd().1 == month {
+            let (_, _, day) = date.ymd();
+            let cell = date.days - first.days + first.weekday();
+            let cell_x = x + (cell % 7) as f32 * CALENDAR_CELL;
+            let cell_y = y + 30.0 + (cell / 7) as f32 * CALENDAR_CELL;
+
+            if self.entry(date).is_some() {
+                draw_rectangle(cell_x + 1.0, cell_y + 1.0, CALENDAR_CELL - 2.0, CALENDAR_CELL - 2.0, PLAYED_DAY_COLOR);
+            }
+            if date == today {
+                draw_rectangle_lines(cell_x, cell_y, CALENDAR_CELL, CALENDAR_CELL, 2.0, TEXT_ACCENT);
+            }
+            let color = if date > today { TEXT_SECONDARY } else { TEXT_PRIMARY };
+            draw_text(&day.to_string(), cell_x + 4.0, cell_y + 17.0, 16.0, color);
+
+            date = Date { days: date.days + 1 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch() {
+        let date = Date::from_ymd(1970, 1, 1);
+        assert_eq!(date.days, 0);
+        assert_eq!(date.weekday(), 3);  // A Thursday
+        assert_eq!(date.name(), "1970-01-01");
+    }
+
+    #[test]
+    fn round_trips_every_day() {
+        for days in -800_000..800_000 {
+            let (year, month, day) = Date { days }.ymd();
+            assert_eq!(Date::from_ymd(year, month, day).days, days);
+        }
+    }
+
+    #[test]
+    fn leap_days() {
+        assert!(Date::parse("2024-02-29").is_some());
+        assert!(Date::parse("2000-02-29").is_some());
+        assert!(Date::parse("2023-02-29").is_none());
+        assert!(Date::parse("1900-02-29").is_none());
+        assert_eq!(Date::parse("2024-03-01").unwrap().days - Date::parse("2024-02-28").unwrap().days, 2);
+        assert_eq!(Date::parse("2100-03-01").unwrap().days - Date::parse("2100-02-28").unwrap().days, 1);
+    }
+
+    #[test]
+    fn parse_rejects_nonsense() {
+        for text in ["", "2024", "2024-13-01", "2024-04-31", "2024-00-10", "yesterday"] {
+            assert!(Date::parse(text).is_none(), "{}", text);
+        }
+        assert_eq!(Date::parse("2026-10-18").unwrap().name(), "2026-10-18");
+    }
+}
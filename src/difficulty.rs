@@ -0,0 +1,123 @@
+// Adaptive difficulty: watches how the run is going and quietly nudges
+// the shadow delay and coin spawn rate to keep the player on the edge.
+
+const EVALUATION_INTERVAL: f32 = 10.0;  // Seconds between adjustments
+const MIN_DELAY_OFFSET: i32 = -8;        // Frames the shadow can gain on the preset delay
+const MAX_DELAY_OFFSET: i32 = 20;        // Frames the shadow can lose
+const MIN_COIN_INTERVAL_SCALE: f32 = 0.6;
+const MAX_COIN_INTERVAL_SCALE: f32 = 1.5;
+
+pub struct AdaptiveDifficulty {
+    pub enabled: bool,
+    evaluation_timer: f32,
+    hits: u32,
+    near_misses: u32,
+    delay_offset: i32,
+    coin_interval_scale: f32,
+}
+
+impl AdaptiveDifficulty {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            evaluation_timer: EVALUATION_INTERVAL,
+            hits: 0,
+            near_misses: 0,
+            delay_offset: 0,
+            coin_interval_scale: 1.0,
+        }
+    }
+
+    pub fn record_hit(&mut self) {
+        self.hits += 1;
+    }
+
+    pub fn record_near_miss(&mut self) {
+        self.near_misses += 1;
+    }
+
+    // Returns true when the tuning changed
+    pub fn update(&mut self, dt: f32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        self.evaluation_timer -= dt;
+        if self.evaluation_timer > 0.0 {
+            return false;
+        }
+        self.evaluation_timer = EVALUATION_INTERVAL;
+
+        if self.hits > 0 {
+            // Struggling: give the player more room and more coins
+            self.delay_offset += 4 * self.hits as i32;
+            self.coin_interval_scale *= 0.85;
+        } else {
+            // Survived the whole window: close in, harder if they're showing off
+            self.delay_offset -= 2 + self.near_misses.min(3) as i32;
+            self.coin_interval_scale *= 1.1;
+        }
+
+        self.delay_offset = self.delay_offset.clamp(MIN_DELAY_OFFSET, MAX_DELAY_OFFSET);
+        self.coin_interval_scale = self.coin_interval_scale.clamp(MIN_COIN_INTERVAL_SCALE, MAX_COIN_INTERVAL_SCALE);
+        self.hits = 0;
+        self.near_misses = 0;
+        true
+    }
+
+    pub fn shadow_delay(&self, base: usize) -> usize {
+        (base as i32 + self.delay_offset).max(1) as usize
+    }
+
+    pub fn coin_spawn_interval(&self, base: f32) -> f32 {
+        base * self.coin_interval_scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_tunes_when_enabled() {
+        let mut adaptive = AdaptiveDifficulty::new(false);
+        adaptive.record_hit();
+        assert!(!adaptive.update(EVALUATION_INTERVAL));
+        assert_eq!(adaptive.shadow_delay(30), 30);
+    }
+
+    #[test]
+    fn tunes_once_per_interval() {
+        let mut adaptive = AdaptiveDifficulty::new(true);
+        assert!(!adaptive.update(EVALUATION_INTERVAL * 0.5));
+        assert!(adaptive.update(EVALUATION_INTERVAL * 0.5));
+        assert!(adaptive.shadow_delay(30) < 30);
+    }
+
+    #[test]
+    fn struggling_stays_within_bounds() {
+        let mut adaptive = AdaptiveDifficulty::new(true);
+        for _ in 0..50 {
+            for _ in 0..5 {
+                adaptive.record_hit();
+            }
+            adaptive.update(EVALUATION_INTERVAL);
+        }
+        assert_eq!(adaptive.shadow_delay(30), (30 + MAX_DELAY_OFFSET) as usize);
+        assert_eq!(adaptive.coin_spawn_interval(2.0), 2.0 * MIN_COIN_INTERVAL_SCALE);
+    }
+
+    #[test]
+    fn showing_off_stays_within_bounds() {
+        let mut adaptive = AdaptiveDifficulty::new(true);
+        for _ in 0..50 {
+            for _ in 0..5 {
+                adaptive.record_near_miss();
+            }
+            adaptive.update(EVALUATION_INTERVAL);
+        }
+        assert_eq!(adaptive.shadow_delay(30), (30 + MIN_DELAY_OFFSET) as usize);
+        assert_eq!(adaptive.shadow_delay(4), 1);
+        assert_eq!(adaptive.coin_spawn_interval(2.0), 2.0 * MAX_COIN_INTERVAL_SCALE);
+    }
+}
@@ -0,0 +1,98 @@
+use macroquad::prelude::*;
+
+use crate::settings::Settings;
+
+// Camera feedback: screen shake, hit-stop and slow motion
+const TRAUMA_DECAY: f32 = 1.5;      // Trauma lost per second
+const MAX_SHAKE_OFFSET: f32 = 12.0; // Pixels at full trauma
+const SHAKE_FREQUENCY: f32 = 40.0;
+
+pub struct Feedback {
+    trauma: f32,              // 0.0 to 1.0, the shake grows with its square
+    hit_stop_frames: u32,
+    slow_motion_timer: f32,
+    shake_intensity: f32,
+    hit_stop_length: u32,
+    slow_motion_scale: f32,
+    reduce_motion: bool,
+}
+
+impl Feedback {
+    pub fn new(settings: &Settings) -> Self {
+        let mut feedback = Self {
+            trauma: 0.0,
+            hit_stop_frames: 0,
+            slow_motion_timer: 0.0,
+            shake_intensity: 1.0,
+            hit_stop_length: 0,
+            slow_motion_scale: 1.0,
+            reduce_motion: false,
+        };
+        feedback.apply_settings(settings);
+        feedback
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.shake_intensity = settings.screen_shake;
+        self.hit_stop_length = settings.hit_stop_frames;
+        self.slow_motion_scale = settings.slow_motion;
+        self.reduce_motion = settings.reduce_motion;
+        if self.reduce_motion {
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.trauma = 0.0;
+        self.hit_stop_frames = 0;
+        self.slow_motion_timer = 0.0;
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        if !self.reduce_motion {
+            self.trauma = (self.trauma + amount * self.shake_intensity).min(1.0);
+        }
+    }
+
+    pub fn hit_stop(&mut self) {
+        if !self.reduce_motion {
+            self.hit_stop_frames = self.hit_stop_length;
+        }
+    }
+
+    pub fn slow_motion(&mut self, duration: f32) {
+        if !self.reduce_motion {
+            self.slow_motion_timer = duration;
+        }
+    }
+
+    pub fn is_slow_motion(&self) -> bool {
+        self.slow_motion_timer > 0.0
+    }
+
+    // Advances the effects in real time and returns how much game time passes this frame
+    pub fn update(&mut self, real_dt: f32) -> f32 {
+        self.trauma = (self.trauma - TRAUMA_DECAY * real_dt).max(0.0);
+
+        if self.hit_stop_frames > 0 {
+            self.hit_stop_frames -= 1;
+            return 0.0;
+        }
+        if self.slow_motion_timer > 0.0 {
+            self.slow_motion_timer -= real_dt;
+            return real_dt * self.slow_motion_scale;
+        }
+        real_dt
+    }
+
+    pub fn shake_offset(&self) -> Vec2 {
+        let shake = self.trauma * self.trauma;
+        if shake <= 0.0 {
+            return Vec2::ZERO;
+        }
+
+        // Layered sines are smooth enough and don't touch the game's random numbers
+        let t = get_time() as f32 * SHAKE_FREQUENCY;
+        vec2((t).sin() * (t * 1.7).cos(), (t * 1.3).cos() * (t * 0.7).sin()) * shake * MAX_SHAKE_OFFSET
+    }
+}
@@ -0,0 +1,92 @@
+use macroquad::experimental::animation::{AnimatedSprite, Animation};
+use macroquad::prelude::*;
+
+use crate::replay::Replay;
+use crate::PLAYER_SIZE;
+
+// A pale, see-through copy of the player following a replay. It's only ever drawn: no collider,
+// nothing to touch, nothing that can touch it
+const GHOST_COLOR: Color = Color::new(0.7, 0.9, 1.0, 0.45);  // Pale blue, nothing like the dark shadow
+const TIME_EPSILON: f32 = 0.0001;  // Float slack when lining frames up with the run's time
+
+pub struct Ghost {
+    pub replay: Replay,
+    texture: Texture2D,
+    sprite: AnimatedSprite,
+    elapsed: f32,     // Game time since the run started
+    frame_start: f32, // Game time at the start of the current frame
+    index: usize,
+    facing: f32,
+}
+
+impl Ghost {
+    pub async fn new(replay: Replay) -> Self {
+        set_pc_assets_folder("assets");
+        let texture = load_texture("player.png").await.expect("Couldn't load player texture");
+        texture.set_filter(FilterMode::Nearest);
+        let sprite = AnimatedSprite::new(
+            12,
+            12,
+            &[
+                Animation { name: "walk".to_string(), row: 0, frames: 6, fps: 12 },
+                Animation { name: "jump".to_string(), row: 2, frames: 3, fps: 12 },
+            ],
+            true,
+        );
+
+        Self {
+            replay,
+            texture,
+            sprite,
+            elapsed: 0.0,
+            frame_start: 0.0,
+            index: 0,
+            facing: 1.0,
+        }
+    }
+
+    // Keep pace with the run. Frames are matched by game time, so the ghost lines up
+    // even when the two runs had different frame rates
+    pub fn advance(&mut self, dt: f32) {
+        self.elapsed += dt;
+        let frames = &self.replay.frames;
+        while self.index < frames.len() && self.frame_start + frames[self.index].dt <= self.elapsed + TIME_EPSILON {
+            self.frame_start += frames[self.index].dt;
+            self.index += 1;
+        }
+    }
+
+    fn movement(&self) -> Option<(Vec2, Vec2)> {
+        let frames = &self.replay.frames;
+        let now = frames.get(self.index)?.position;
+        let before = frames.get(self.index.saturating_sub(1)).map_or(now, |frame| frame.position);
+        Some((now, now - before))
+    }
+
+    // Once the recorded run is over, the ghost is gone
+    pub fn draw(&mut self) {
+        let Some((pos, moved)) = self.movement() else {
+            return;
+        };
+        if moved.x != 0.0 {
+            self.facing = moved.x.signum();
+        }
+        self.sprite.set_animation(if moved.y != 0.0 { 1 } else { 0 });
+        if moved != Vec2::ZERO {
+            self.sprite.update();
+        }
+
+        draw_texture_ex(
+            &self.texture,
+            pos.x,
+            pos.y,
+            GHOST_COLOR,
+            DrawTextureParams {
+                dest_size: Some(PLAYER_SIZE),
+                source: Some(self.sprite.frame().source_rect),
+                flip_x: self.facing < 0.0,
+                ..Default::default()
+            },
+        );
+    }
+}
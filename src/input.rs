@@ -0,0 +1,129 @@
+use macroquad::prelude::*;
+
+// Which keys drive a player. In co-op the keyboard is split in two, and rewind and swap are solo only
+pub struct Bindings {
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub jump: KeyCode,
+    pub dash: KeyCode,
+    pub rewind: Option<KeyCode>,
+    pub swap: Option<KeyCode>,
+}
+
+pub const SOLO: Bindings = Bindings {
+    left: KeyCode::Left,
+    right: KeyCode::Right,
+    jump: KeyCode::Space,
+    dash: KeyCode::LeftShift,
+    rewind: Some(KeyCode::R),
+    swap: Some(KeyCode::E),
+};
+
+pub const PLAYER_ONE: Bindings = Bindings {
+    left: KeyCode::Left,
+    right: KeyCode::Right,
+    jump: KeyCode::Up,
+    dash: KeyCode::RightShift,
+    rewind: None,
+    swap: None,
+};
+
+pub const PLAYER_TWO: Bindings = Bindings {
+    left: KeyCode::A,
+    right: KeyCode::D,
+    jump: KeyCode::W,
+    dash: KeyCode::LeftShift,
+    rewind: None,
+    swap: None,
+};
+
+// What the player asked for on one frame. Read from the keyboard, or played back from a replay
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Controls {
+    pub left: bool,
+    pub right: bool,
+    pub jump_pressed: bool,
+    pub jump_held: bool,  // Releasing early cuts the jump short
+    pub dash_pressed: bool,
+    pub rewind_held: bool,
+    pub swap_pressed: bool,
+}
+
+impl Controls {
+    pub fn read(bindings: &Bindings) -> Self {
+        Self {
+            left: is_key_down(bindings.left),
+            right: is_key_down(bindings.right),
+            jump_pressed: is_key_pressed(bindings.jump),
+            jump_held: is_key_down(bindings.jump),
+            dash_pressed: is_key_pressed(bindings.dash),
+            rewind_held: bindings.rewind.is_some_and(is_key_down),
+            swap_pressed: bindings.swap.is_some_and(is_key_pressed),
+        }
+    }
+
+    // Keeps presses around until a simulation tick uses them, for when ticks and frames don't line up
+    pub fn latch(&mut self, newer: Controls) {
+        *self = Self {
+            jump_pressed: self.jump_pressed || newer.jump_pressed,
+            dash_pressed: self.dash_pressed || newer.dash_pressed,
+            swap_pressed: self.swap_pressed || newer.swap_pressed,
+            ..newer
+        };
+    }
+
+    // The latched controls for one tick. Presses only count once, what's held stays held
+    pub fn take(&mut self) -> Controls {
+        let controls = *self;
+        self.jump_pressed = false;
+        self.dash_pressed = false;
+        self.swap_pressed = false;
+        controls
+    }
+
+    // One bit per control, for replay files
+    pub fn to_bits(self) -> u8 {
+        [self.left, self.right, self.jump_pressed, self.jump_held, self.dash_pressed, self.rewind_held, self.swap_pressed]
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, down)| bits | ((*down as u8) << i))
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        let down = |i: u8| bits & (1 << i) != 0;
+        Self {
+            left: down(0),
+            right: down(1),
+            jump_pressed: down(2),
+            jump_held: down(3),
+            dash_pressed: down(4),
+            rewind_held: down(5),
+            swap_pressed: down(6),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_round_trip() {
+        for bits in 0..128 {
+            assert_eq!(Controls::from_bits(bits).to_bits(), bits);
+        }
+        let controls = Controls { left: true, jump_held: true, swap_pressed: true, ..Controls::default() };
+        assert!(Controls::from_bits(controls.to_bits()) == controls);
+    }
+
+    #[test]
+    fn latched_presses_count_once() {
+        let mut latched = Controls::default();
+        latched.latch(Controls { jump_pressed: true, jump_held: true, ..Controls::default() });
+        latched.latch(Controls { jump_held: true, ..Controls::default() });
+        let first = latched.take();
+        assert!(first.jump_pressed && first.jump_held);
+        let second = latched.take();
+        assert!(!second.jump_pressed && second.jump_held);
+    }
+}
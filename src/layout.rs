@@ -0,0 +1,287 @@
+use macroquad::prelude::*;
+
+use crate::config::PhysicsConfig;
+use crate::mode::Abilities;
+use crate::rng::SeededRng;
+use crate::{CACTUS_SIZES, GROUND_SIZE, GROUND_Y, PLATFORM_SIZE, PLAYER_SIZE, WINDOW_WIDTH};
+
+// Single-screen platform layouts: the classic one, and generated ones that are
+// checked against the jump physics before anyone gets to play them
+const MAX_ATTEMPTS: u32 = 50;         // Generated layouts to try before falling back to the classic one
+const REACH_MARGIN: f32 = 0.9;        // Only count on this much of a perfect jump
+const ROWS: [f32; 4] = [485.0, 385.0, 285.0, 185.0];
+const WIDTHS: [f32; 3] = [200.0, 140.0, 100.0];
+const MIN_GAP: f32 = 60.0;            // Between two platforms on the same row
+const MAX_GAP: f32 = 300.0;
+const MOVING_CHANCE: usize = 4;       // One platform in four moves
+const MOVING_TRAVEL: f32 = 150.0;
+const MAX_CACTI: usize = 2;           // Per platform, there's always at least one
+
+#[derive(Clone)]
+pub struct LayoutPlatform {
+    pub pos: Vec2,
+    pub size: Vec2,
+    pub speed: f32,          // 0.0 for static platforms, 1.0 (or -1.0 to start leftwards) for moving ones
+    pub range: (f32, f32),   // Left and right turning points of a moving platform
+    pub cacti: Vec<(f32, f32)>,  // Offset from the left edge and size
+}
+
+impl LayoutPlatform {
+    pub fn new(pos: Vec2, size: Vec2, speed: f32) -> Self {
+        Self { pos, size, speed, range: (150.0, 500.0), cacti: Vec::new() }
+    }
+
+    fn with_cacti(mut self, cacti: &[(f32, f32)]) -> Self {
+        self.cacti = cacti.to_vec();
+        self
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.pos.x, self.pos.y, self.size.x, self.size.y)
+    }
+
+    // Everywhere the platform can be: moving ones sweep their whole range
+    pub fn area(&self) -> Rect {
+        if self.speed == 0.0 {
+            return self.rect();
+        }
+        let left = self.pos.x.min(self.range.0);
+        let right = self.pos.x.max(self.range.1) + self.size.x;
+        Rect::new(left, self.pos.y, right - left, self.size.y)
+    }
+}
+
+// How far the player can get in one go, worked out from the physics config and the moves the mode allows
+pub struct JumpReach {
+    speed: f32,
+    jump: f32,
+    double_jump: f32,
+    rise_gravity: f32,
+    fall_gravity: f32,
+}
+
+impl JumpReach {
+    pub fn new(physics: &PhysicsConfig, abilities: Abilities) -> Self {
+        Self {
+            speed: physics.max_speed,
+            jump: physics.jump_force.abs(),
+            double_jump: if abilities.double_jump { physics.double_jump_force.abs() } else { 0.0 },
+            rise_gravity: physics.rise_gravity,
+            fall_gravity: physics.fall_gravity,
+        }
+    }
+
+    // Time in the air for a jump that lands `rise` pixels higher (negative for lower), None if it's out of reach
+    pub fn air_time(&self, rise: f32) -> Option<f32> {
+        let single = self.jump * self.jump / (2.0 * self.rise_gravity);
+        let double = single + self.double_jump * self.double_jump / (2.0 * self.rise_gravity);
+
+        let (peak, rising_time) = if rise <= single * REACH_MARGIN {
+            (single, self.jump / self.rise_gravity)
+        } else if rise <= double * REACH_MARGIN {
+            (double, (self.jump + self.double_jump) / self.rise_gravity)
+        } else {
+            return None;
+        };
+        Some(rising_time + (2.0 * (peak - rise) / self.fall_gravity).sqrt())
+    }
+
+    // Can the player get from standing on `from` to standing on `to`?
+    pub fn can_reach(&self, from: Rect, to: Rect) -> bool {
+        let Some(time) = self.air_time(from.y - to.y) else {
+            return false;
+        };
+        // Jumping up at a platform overhead bumps into it. The player has to stand clear of one of its edges
+        let overhead = to.y < from.y && to.left() < from.right() && from.left() < to.right();
+        let clear = from.left() <= to.left() - PLAYER_SIZE.x || from.right() >= to.right() + PLAYER_SIZE.x;
+        if overhead && !clear {
+            return false;
+        }
+        self.within_run(from, to, time)
+    }
+
+    // Can the player, standing on `from`, get any part of their body into `zone`? For coins and exits
+    pub fn can_touch(&self, from: Rect, zone: Rect) -> bool {
+        let Some(time) = self.air_time(from.y - PLAYER_SIZE.y - zone.bottom()) else {
+            return false;
+        };
+        self.within_run(from, zone, time)
+    }
+
+    fn within_run(&self, from: Rect, to: Rect, time: f32) -> bool {
+        let gap = (to.left() - from.right()).max(from.left() - to.right()).max(0.0);
+        gap <= self.speed * time * REACH_MARGIN
+    }
+}
+
+#[derive(Clone)]
+pub struct Layout {
+    pub platforms: Vec<LayoutPlatform>,  // The first one is always the ground
+}
+
+impl Layout {
+    // The hand-made layout the game started with, for levels that don't have their own
+    pub fn classic() -> Self {
+        Self {
+            platforms: vec![
+                LayoutPlatform::new(vec2(0.0, GROUND_Y), GROUND_SIZE, 0.0).with_cacti(&[(150.0, 48.0), (600.0, 36.0)]),
+                // Moving platforms
+                LayoutPlatform::new(vec2(100.0, 100.0), PLATFORM_SIZE, 1.0),
+                LayoutPlatform::new(vec2(500.0, 500.0), PLATFORM_SIZE, 1.0),
+                // Static platforms
+                LayoutPlatform::new(vec2(50.0, 200.0), PLATFORM_SIZE, 0.0).with_cacti(&[(20.0, 36.0)]),
+                LayoutPlatform::new(vec2(550.0, 200.0), PLATFORM_SIZE, 0.0).with_cacti(&[(140.0, 48.0)]),
+                LayoutPlatform::new(vec2(300.0, 300.0), PLATFORM_SIZE, 0.0).with_cacti(&[(70.0, 60.0)]),
+                LayoutPlatform::new(vec2(50.0, 400.0), PLATFORM_SIZE, 0.0).with_cacti(&[(30.0, 48.0)]),
+                LayoutPlatform::new(vec2(550.0, 400.0), PLATFORM_SIZE, 0.0).with_cacti(&[(150.0, 36.0)]),
+            ],
+        }
+    }
+
+    // A new layout from the seed, regenerated until it passes the checks
+    pub fn generate(seed: u64, physics: &PhysicsConfig, abilities: Abilities) -> Self {
+        let reach = JumpReach::new(physics, abilities);
+        let mut rng = SeededRng::new(seed);
+
+        for _ in 0..MAX_ATTEMPTS {
+            let layout = Self::random(&mut rng);
+            if layout.problems(&reach).is_empty() {
+                return layout;
+            }
+        }
+        warn!("no playable layout for seed {} after {} attempts, using the classic one", seed, MAX_ATTEMPTS);
+        Self::classic()
+    }
+
+    fn random(rng: &mut SeededRng) -> Self {
+        let mut platforms = vec![LayoutPlatform::new(vec2(0.0, GROUND_Y), GROUND_SIZE, 0.0)];
+
+        // Rows of one to three platforms, filled left to right
+        for y in ROWS {
+            let mut x = rng.gen_range(0.0, MAX_GAP * 0.5);
+            loop {
+                let width = WIDTHS[rng.gen_index(WIDTHS.len())];
+                let moving = rng.gen_index(MOVING_CHANCE) == 0;
+                let travel = if moving { MOVING_TRAVEL } else { 0.0 };
+                if x + width + travel > WINDOW_WIDTH {
+                    break;
+                }
+
+                let speed = if moving { 1.0 } else { 0.0 };
+                let mut platform = LayoutPlatform::new(vec2(x, y), vec2(width, PLATFORM_SIZE.y), speed);
+                platform.range = (x, x + travel);
+                platforms.push(platform);
+
+                x += width + travel + rng.gen_range(MIN_GAP, MAX_GAP);
+            }
+        }
+
+        // Cacti come from the seed too, so the same seed always makes the same layout
+        for platform in platforms.iter_mut() {
+            let width = platform.size.x;
+            platform.cacti = (0..=rng.gen_index(MAX_CACTI))
+                .map(|_| {
+                    let size = rng.gen_range(CACTUS_SIZES.0, CACTUS_SIZES.1).min(width);
+                    (rng.gen_range(0.0, width - size), size)
+                })
+                .collect();
+        }
+
+        Self { platforms }
+    }
+
+    // Which platforms can be reached, walking and jumping from the ground
+    pub fn reachable(&self, reach: &JumpReach) -> Vec<bool> {
+        let mut reached = vec![false; self.platforms.len()];
+        let mut to_visit = vec![0];
+        reached[0] = true;
+
+        while let Some(from) = to_visit.pop() {
+            let from_area = self.platforms[from].area();
+            for (to, platform) in self.platforms.iter().enumerate() {
+                if !reached[to] && reach.can_reach(from_area, platform.area()) {
+                    reached[to] = true;
+                    to_visit.push(to);
+                }
+            }
+        }
+        reached
+    }
+
+    // Everything that makes the layout unfair, empty if it's fine
+    pub fn problems(&self, reach: &JumpReach) -> Vec<String> {
+        let mut problems = Vec::new();
+        let areas: Vec<Rect> = self.platforms.iter().map(|platform| platform.area()).collect();
+
+        for (i, reached) in self.reachable(reach).into_iter().enumerate() {
+            if !reached {
+                problems.push(format!("platform at ({:.0}, {:.0}) can't be reached", areas[i].x, areas[i].y));
+            }
+        }
+
+        for (i, area) in areas.iter().enumerate() {
+            // With only one way out, the shadow following the trail in would corner the player.
+            // The ground spans the screen, there's always room to run around on it
+            let exits = (0..areas.len()).filter(|j| *j != i && reach.can_reach(*area, areas[*j])).count();
+            if i > 0 && exits < 2 {
+                problems.push(format!("platform at ({:.0}, {:.0}) is a dead end", area.x, area.y));
+            }
+
+            // A platform right above leaves no room to stand
+            let cramped = areas.iter().any(|above| {
+                above.y < area.y
+                    && area.y - above.bottom() < PLAYER_SIZE.y
+                    && above.left() < area.right()
+                    && area.left() < above.right()
+            });
+            if cramped {
+                problems.push(format!("no room to stand on the platform at ({:.0}, {:.0})", area.x, area.y));
+            }
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reach() -> JumpReach {
+        JumpReach::new(&PhysicsConfig::default(), Abilities::default())
+    }
+
+    #[test]
+    fn generated_layouts_are_fair() {
+        for seed in 0..20 {
+            let layout = Layout::generate(seed, &PhysicsConfig::default(), Abilities::default());
+            let problems = layout.problems(&reach());
+            assert!(problems.is_empty(), "seed {}: {:?}", seed, problems);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_layout() {
+        let rects = |seed| -> Vec<(Rect, Vec<(f32, f32)>)> {
+            Layout::generate(seed, &PhysicsConfig::default(), Abilities::default())
+                .platforms
+                .into_iter()
+                .map(|platform| (platform.area(), platform.cacti))
+                .collect()
+        };
+        assert_eq!(rects(42), rects(42));
+    }
+
+    #[test]
+    fn dead_end_is_rejected() {
+        // The only way off the platform is back down to the ground
+        let layout = Layout {
+            platforms: vec![
+                LayoutPlatform::new(vec2(0.0, GROUND_Y), GROUND_SIZE, 0.0),
+                LayoutPlatform::new(vec2(300.0, 485.0), PLATFORM_SIZE, 0.0),
+            ],
+        };
+        let problems = layout.problems(&reach());
+        assert_eq!(problems, vec!["platform at (300, 485) is a dead end".to_string()]);
+    }
+}
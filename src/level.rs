@@ -0,0 +1,196 @@
+use macroquad::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::background::sun_height;
+use crate::config::parse_entries;
+use crate::layout::{Layout, LayoutPlatform};
+use crate::{PLATFORM_SIZE, SHADOW_COLOR};
+
+// Levels live in their own data files, the default one is also embedded at build time
+pub const LEVELS_FOLDER: &str = "assets/levels";
+pub const DEFAULT_LEVEL: &str = "desert";
+const EMBEDDED_DEFAULT_LEVEL: &str = include_str!("../assets/levels/desert.txt");
+const SHADE_ALPHA: f32 = 0.3;
+const SHADE_EDGE: f32 = 6.0;  // Soft edge drawn around shade zones
+const MAX_SHADOW_OFFSET: f32 = 20.0;  // Stays well under the hitbox width, so standing still never outsmarts the shadow
+
+// Where the sun is and how it bends the shadow
+#[derive(Clone, Copy)]
+pub struct SunConfig {
+    pub day_length: f32,   // Seconds of survival for a full day
+    pub start_time: f32,   // Time of day a run starts at (0.0 = sunrise, 0.25 = noon, 0.5 = sunset, 0.75 = midnight)
+    pub locked: bool,      // The sun stays at `start_time` for the whole run
+    pub max_offset: f32,   // Pixels the shadow is pushed away from a low sun
+    pub noon_lag: f32,     // Shadow delay multiplier with the sun overhead
+    pub dusk_lag: f32,     // Shadow delay multiplier with the sun on the horizon
+}
+
+impl Default for SunConfig {
+    fn default() -> Self {
+        Self {
+            day_length: 120.0,
+            start_time: 0.1,
+            locked: false,
+            max_offset: MAX_SHADOW_OFFSET,
+            noon_lag: 0.8,
+            dusk_lag: 1.3,
+        }
+    }
+}
+
+impl SunConfig {
+    pub fn time_of_day(&self, run_time: f32) -> f32 {
+        if self.locked {
+            return self.start_time.rem_euclid(1.0);
+        }
+        (self.start_time + run_time / self.day_length).rem_euclid(1.0)
+    }
+
+    // 0.0 with the sun overhead (or at midnight), 1.0 with it on the horizon
+    pub fn stretch(&self, time_of_day: f32) -> f32 {
+        1.0 - sun_height(time_of_day).abs()
+    }
+
+    // The shadow is cast away from the sun. After sunset the offset fades out over the twilight
+    pub fn shadow_offset(&self, time_of_day: f32) -> Vec2 {
+        let daylight = ((sun_height(time_of_day) + 0.25) / 0.25).clamp(0.0, 1.0);
+        vec2((time_of_day * TAU).cos() * self.max_offset * daylight, 0.0)
+    }
+
+    pub fn shadow_delay(&self, base: usize, time_of_day: f32) -> usize {
+        let lag = self.noon_lag + (self.dusk_lag - self.noon_lag) * self.stretch(time_of_day);
+        ((base as f32 * lag).round() as usize).max(1)
+    }
+}
+
+pub struct Level {
+    pub id: String,    // File name without the extension
+    pub name: String,  // Shown to the player
+    pub sun: SunConfig,
+    pub shade: Vec<Rect>,  // Zones the shadow can't follow the player into
+    pub layout: Option<Layout>,  // The classic layout is used when a level doesn't have one
+    pub coin_zones: Vec<Rect>,   // Where coins show up, anywhere on screen if there are none
+    pub exits: Vec<Rect>,
+}
+
+impl Level {
+    pub fn load(id: &str) -> Self {
+        let path = format!("{}/{}.txt", LEVELS_FOLDER, id);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) if id == DEFAULT_LEVEL => EMBEDDED_DEFAULT_LEVEL.to_string(),
+            Err(e) => {
+                warn!("couldn't read level `{}` ({}), using the default", path, e);
+                EMBEDDED_DEFAULT_LEVEL.to_string()
+            }
+        };
+        Self::from_text(id, &text)
+    }
+
+    pub fn from_text(id: &str, text: &str) -> Self {
+        let (level, errors) = Self::parse(id, text);
+        for error in errors {
+            warn!("level `{}` {}", id, error);
+        }
+        level
+    }
+
+    // The level along with every line that couldn't be used, for the level check
+    pub fn parse(id: &str, text: &str) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut level = Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            sun: SunConfig::default(),
+            shade: Vec::new(),
+            layout: None,
+            coin_zones: Vec::new(),
+            exits: Vec::new(),
+        };
+
+        for entry in parse_entries(text) {
+            if let Err(e) = level.set(&entry.section, entry.key, entry.value) {
+                errors.push(format!("line {}: {}", entry.line, e));
+            }
+        }
+
+        (level, errors)
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: &str) -> Result<(), String> {
+        let number = || value.parse::<f32>().map_err(|e| e.to_string());
+        let numbers = || {
+            value
+                .split_whitespace()
+                .map(|n| n.parse::<f32>().map_err(|e| e.to_string()))
+                .collect::<Result<Vec<f32>, String>>()
+        };
+        let zone = || match numbers()?[..] {
+            [x, y, w, h] if w > 0.0 && h > 0.0 => Ok(Rect::new(x, y, w, h)),
+            _ => Err(format!("`{}` expects x y width height", key)),
+        };
+        match (section, key) {
+            ("", "name") => self.name = value.to_string(),
+            ("sun", "day_length") => self.sun.day_length = number()?.max(1.0),
+            ("sun", "start_time") => self.sun.start_time = number()?,
+            ("sun", "locked") => self.sun.locked = value == "true",
+            ("sun", "max_offset") => match number()? {
+                offset if (0.0..=MAX_SHADOW_OFFSET).contains(&offset) => self.sun.max_offset = offset,
+                _ => return Err(format!("`max_offset` has to be between 0 and {}", MAX_SHADOW_OFFSET)),
+            },
+            ("sun", "noon_lag") => self.sun.noon_lag = number()?.max(0.1),
+            ("sun", "dusk_lag") => self.sun.dusk_lag = number()?.max(0.1),
+            ("shade", "zone") => self.shade.push(zone()?),
+            ("layout", "platform") => match numbers()?[..] {
+                [x, y, width] => self.add_platform(LayoutPlatform::new(vec2(x, y), vec2(width, PLATFORM_SIZE.y), 0.0)),
+                _ => return Err("`platform` expects x y width".to_string()),
+            },
+            ("layout", "moving") => match numbers()?[..] {
+                [x, y, width, left, right] if left <= right => {
+                    let mut platform = LayoutPlatform::new(vec2(x, y), vec2(width, PLATFORM_SIZE.y), 1.0);
+                    platform.range = (left, right);
+                    self.add_platform(platform);
+                }
+                _ => return Err("`moving` expects x y width left right".to_string()),
+            },
+            ("layout", "cactus") => match numbers()?[..] {
+                [x, y, size] => self.add_cactus(x, y, size)?,
+                _ => return Err("`cactus` expects x y size".to_string()),
+            },
+            ("layout", "coin_zone") => self.coin_zones.push(zone()?),
+            ("layout", "exit") => self.exits.push(zone()?),
+            _ => return Err(format!("unknown key `{}`", key)),
+        }
+        Ok(())
+    }
+
+    fn add_platform(&mut self, platform: LayoutPlatform) {
+        self.layout.get_or_insert(Layout { platforms: Vec::new() }).platforms.push(platform);
+    }
+
+    // Cacti stand on whichever static platform is under them
+    fn add_cactus(&mut self, x: f32, y: f32, size: f32) -> Result<(), String> {
+        let platform = self
+            .layout
+            .iter_mut()
+            .flat_map(|layout| layout.platforms.iter_mut())
+            .find(|p| p.speed == 0.0 && (p.pos.y - y).abs() < 1.0 && x >= p.pos.x && x + size <= p.pos.x + p.size.x)
+            .ok_or("cactus isn't standing on a static platform")?;
+        platform.cacti.push((x - platform.pos.x, size));
+        Ok(())
+    }
+
+    // The player is in the shade when the middle of their body is
+    pub fn in_shade(&self, rect: Rect) -> bool {
+        self.shade.iter().any(|zone| zone.contains(rect.center()))
+    }
+
+    pub fn draw_shade(&self) {
+        let color = Color::new(SHADOW_COLOR.r, SHADOW_COLOR.g, SHADOW_COLOR.b, SHADE_ALPHA);
+        let edge = Color::new(SHADOW_COLOR.r, SHADOW_COLOR.g, SHADOW_COLOR.b, SHADE_ALPHA * 0.4);
+        for zone in &self.shade {
+            draw_rectangle(zone.x - SHADE_EDGE, zone.y, zone.w + SHADE_EDGE * 2.0, zone.h, edge);
+            draw_rectangle(zone.x, zone.y, zone.w, zone.h, color);
+        }
+    }
+}
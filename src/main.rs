@@ -3,25 +3,62 @@ use macroquad::experimental::animation::{AnimatedSprite, Animation};
 use macroquad::prelude::*;
 use macroquad_platformer::*;
 use macroquad::rand::*;
-use macroquad::audio::*;
+
+mod background;
+mod camera;
+mod canvas;
+mod config;
+mod daily;
+mod difficulty;
+mod feedback;
+mod ghost;
+mod input;
+mod layout;
+mod level;
+mod mode;
+mod particles;
+mod replay;
+mod rewind;
+mod rng;
+mod runner;
+mod scores;
+mod settings;
+mod time_attack;
+mod validate;
+
+use background::{draw_background, sun_height, tinted, Palette};
+use camera::GameCamera;
+use canvas::{Canvas, ScaleMode};
+use config::{CoinTierConfig, ConfigFile, GameConfig, PhysicsConfig, Preset};
+use daily::{DailyLog, Date, Mutator};
+use difficulty::AdaptiveDifficulty;
+use feedback::Feedback;
+use ghost::Ghost;
+use input::{Controls, PLAYER_ONE, PLAYER_TWO, SOLO};
+use layout::Layout;
+use level::{Level, DEFAULT_LEVEL};
+use mode::{Abilities, GameMode};
+use particles::{Effect, ParticleSystem};
+use replay::{list_replays, Playback, Replay, ReplayFrame, REPLAYS_FOLDER};
+use rewind::Rewind;
+use rng::{random_seed, SeededRng};
+use runner::{Chunk, RunnerTrack};
+use scores::{HighScore, HighScores};
+use settings::Settings;
+use time_attack::{format_delta, format_ticks, BestSplits, TimeAttack, MAX_TICKS_PER_FRAME, TICK};
+use validate::validate_level;
 
 const WINDOW_WIDTH: f32 = 800.0;
 const WINDOW_HEIGHT: f32 = 600.0;
 
-// Game Constants
-const GRAVITY: f32 = 500.0;
-const PLAYER_SPEED: f32 = 150.0;
-const JUMP_FORCE: f32 = -350.0;
-const PLATFORM_SPEED: f32 = 50.0;
-const SHADOW_FRAMES_DELAY: usize = 75;
-
 // Size Constants
 const PLAYER_SIZE: Vec2 = vec2(12.0 *4., 12.0 *4.);
 const GROUND_SIZE: Vec2 = vec2(800.0, 12.0);
 const PLATFORM_SIZE: Vec2 = vec2(200.0, 12.0);
+const GROUND_Y: f32 = 585.0;                     // Top of the ground
+const CACTUS_SIZES: (f32, f32) = (36.0, 60.0);   // Smallest and largest cactus
 
 // Colors
-const PLAYER_COLOR: Color = Color::new(0.45, 0.26, 0.20, 1.0);  // Rustic brown for player
 const PLATFORM_COLOR: Color = Color::new(0.76, 0.60, 0.42, 1.0);  // Sandy beige for moving platforms
 const STATIC_PLATFORM_COLOR: Color = Color::new(0.87, 0.68, 0.45, 1.0);  // Light sand for static platforms
 const SHADOW_COLOR: Color = Color::new(0.2, 0.1, 0.05, 0.6);  // Dark sepia shadow
@@ -35,15 +72,83 @@ const TEXT_WARNING: Color = Color::new(0.7, 0.3, 0.2, 1.0);       // Reddish bro
 const TEXT_GOLD: Color = Color::new(0.85, 0.6, 0.2, 1.0);         // Desert gold - for high scores
 
 // Lives system
-const INITIAL_LIVES: i32 = 3;
-const INVULNERABILITY_DURATION: f32 = 3.0; // Seconds of invulnerability after getting hit
 const FLASH_FREQUENCY: f32 = 10.0; // Higher number = faster flashing
 
+// Camera feedback
+const HIT_TRAUMA: f32 = 0.6;
+const NEAR_MISS_TRAUMA: f32 = 0.15;
+const FINAL_SLOW_MOTION_DURATION: f32 = 1.5;  // Real seconds of slow motion before game over
+
+// Window handling
+const WINDOW_SIZE_SAVE_DELAY: f32 = 0.5;  // Wait for the resize to settle before saving the size
+
+// Rewind
+const REWIND_TINT: Color = Color::new(0.35, 0.45, 0.75, 0.18);  // Washes over the screen while rewinding
+
+// Swap with shadow
+const SWAP_COOLDOWN: f32 = 6.0;
+// Where to try putting the player if the shadow's spot is inside a platform: as is, a bit higher, then to the sides
+const SWAP_NUDGES: [Vec2; 7] = [
+    vec2(0.0, 0.0),
+    vec2(0.0, -8.0),
+    vec2(0.0, -16.0),
+    vec2(0.0, -24.0),
+    vec2(-16.0, 0.0),
+    vec2(16.0, 0.0),
+    vec2(0.0, -48.0),
+];
+
+// Endless modes
+const PIXELS_PER_METER: f32 = 50.0;         // For scoring height and distance
+
+// Climber mode
+const CLIMB_SCROLL_SPEED: f32 = 15.0;       // Pixels per second the view creeps up at the start
+const CLIMB_SCROLL_RAMP: f32 = 0.005;       // Added to the scroll speed per pixel climbed
+const CLIMB_MAX_SCROLL_SPEED: f32 = 60.0;
+const CLIMB_GAP_MIN: f32 = 80.0;            // Vertical space between two platforms
+const CLIMB_GAP_MAX: f32 = 120.0;
+const CLIMB_CULL_MARGIN: f32 = 20.0;        // How far below the view a platform goes before it's recycled
+const CLIMBER_PLATFORM_COUNT: usize = 10;   // Enough to fill the view with some to spare above it
+const CLIMBER_PLATFORM_WIDTHS: [f32; 3] = [200.0, 140.0, 100.0];
+const CLIMBER_MOVING_EVERY: usize = 4;      // Every fourth platform moves
+
+// Runner mode
+const RUN_SCROLL_SPEED: f32 = 70.0;         // Pixels per second at the start
+const RUN_SCROLL_RAMP: f32 = 0.006;         // Added to the scroll speed per pixel travelled
+const RUN_MAX_SCROLL_SPEED: f32 = 130.0;    // Stays below the player's top speed
+const RUN_LOOKAHEAD: f32 = 400.0;           // Track is built this far past the right of the view
+const RUN_CULL_MARGIN: f32 = 600.0;         // Left behind this far, platforms and coins are reused or dropped.
+                                            // Further than a full rewind can scroll back
+const RUN_COIN_LIFETIME: f32 = 60.0;        // Track coins stay until they scroll away
+
 // Add these constants at the top
 const COIN_SIZE: Vec2 = vec2(12.0 * 3., 12.0 * 3.);  // Scale the 12x12 sprite by 3
-const COIN_SPAWN_INTERVAL: f32 = 3.0;  // Spawn a new coin every 3 seconds
-const COIN_LIFETIME: f32 = 5.0;  // Coins disappear after 5 seconds
-const COIN_POINTS: i32 = 10;     // Points earned per coin
+
+// Combo system
+const COMBO_WINDOW: f32 = 2.0;  // Seconds before the multiplier drops one step
+const COMBO_MAX_MULTIPLIER: i32 = 5;
+const POPUP_DURATION: f32 = 1.0;  // How long the "+points" text stays on screen
+const POPUP_RISE_SPEED: f32 = 40.0;
+
+// Near-miss system
+const NEAR_MISS_DISTANCE: f32 = 30.0;  // Gap (in pixels) between hitboxes that counts as a close call
+const NEAR_MISS_POINTS: f32 = 5.0;     // Score bonus for escaping a close call
+const DANGER_RANGE: f32 = 200.0;       // The danger meter starts filling below this gap
+
+// Shadow look
+const AFTERIMAGE_COUNT: usize = 4;
+const AFTERIMAGE_SPACING: usize = 2;   // Trail frames between two afterimages at noon
+const AFTERIMAGE_EXTRA_SPACING: f32 = 5.0;  // Added as the sun gets low, stretching the shadow
+const SHADOW_NIGHT_OPACITY: f32 = 0.45;
+const AFTERIMAGE_ALPHA: f32 = 0.5;
+const WOBBLE_AMOUNT: f32 = 2.0;        // Pixels of side-to-side wobble
+const WOBBLE_SPEED: f32 = 6.0;
+const SHADE_FADE_SPEED: f32 = 3.0;     // How fast the shadow dissolves in the shade and comes back
+
+// Co-op
+const PLAYER_TWO_START: Vec2 = vec2(500.0, 500.0);  // Moved to the nearest free spot of the layout
+const PLAYER_TWO_TINT: Color = Color::new(0.6, 1.0, 0.7, 1.0);  // A greenish second player
+const TRAIL_START: Vec2 = vec2(50.0, 500.0);  // Where the shadow starts out, steered or not
 
 
 #[derive(PartialEq)]
@@ -52,53 +157,7 @@ enum GameScreen {
     Playing,
     Paused,
     GameOver,
-}
-
-struct GameAudio {
-    background_music: Sound,
-    // jump_sound: Sound,
-    // game_over_sound: Sound,
-}
-
-impl GameAudio {
-    async fn new() -> Self {
-        set_pc_assets_folder("assets");
-        Self {
-            background_music: load_sound("background.ogg").await.expect("Failed to load background music"),
-            // jump_sound: load_sound("jump.ogg").await.expect("Failed to load jump sound"),
-            // game_over_sound: load_sound("game_over.ogg").await.expect("Failed to load game over sound"),
-        }
-    }
-
-    fn play_background(&self) {
-        // if !is_sound_playing(&self.background_music) {
-            play_sound(&self.background_music, PlaySoundParams {
-                looped: true,
-                volume: 0.5,
-            });
-        // }
-    }
-
-    // fn play_jump(&self) {
-    //     play_audio(&self.jump_sound, PlaySoundParams {
-    //         looped: false,
-    //         volume: 0.8,
-    //     });
-    // }
-    //
-    // fn play_game_over(&self) {
-    //     stop_audio(&self.background_music);
-    //     play_audio(&self.game_over_sound, PlaySoundParams {
-    //         looped: false,
-    //         volume: 1.0,
-    //     });
-    // }
-
-    fn stop_all(&self) {
-        stop_sound(&self.background_music);
-        // stop_audio(&self.jump_sound);
-        // stop_audio(&self.game_over_sound);
-    }
+    Results,  // A finished time attack
 }
 
 // Game State
@@ -108,24 +167,99 @@ struct GameState {
     shadow: Shadow,
     platforms: Vec<Platform>,
     score: f32,
+    run_time: f32,  // Seconds survived, drives the day/night cycle
     screen: GameScreen,
-    high_score: f32,
+    mode: GameMode,
+    camera: GameCamera,
+    track: RunnerTrack,
+    seed: u64,                   // Drives the generated layouts, the runner's track and the coins
+    seed_override: Option<u64>,  // From `--seed`, to replay one of those
+    coin_rng: SeededRng,
+    mutators: Vec<Mutator>,      // The daily challenge's twists on the rules
+    daily_log: DailyLog,
+    daily_date: Date,            // The day the current daily run belongs to
+    recording: Replay,           // The current run, frame by frame
+    playback: Option<Playback>,  // From `--replay`, the run is played back instead of read from the keyboard
+    saved_replay: Option<String>,  // Where the last run's replay went, for the game over screen
+    ghost_replay: Option<(String, Replay)>,  // The replay picked to race against, with its path
+    ghost: Option<Ghost>,
+    coop: bool,                  // Picked in the menu, for the modes that allow it
+    shared_lives: bool,          // Co-op players draw from one pool of lives instead of their own
+    partner: Option<Partner>,    // The second player of a co-op run
+    player_out: bool,            // The first player ran out of their own lives and sits the co-op run out
+    hunter: Option<Hunter>,      // The shadow of a versus run, steered by the second player
+    time_attack: TimeAttack,
+    best_splits: BestSplits,
+    tick_controls: Controls,     // Latched between time attack ticks
+    tick_accumulator: f32,
+    high_scores: HighScores,
+    new_high_score: bool,
+    adaptive: AdaptiveDifficulty,
     lives: i32,
     invulnerable_timer: f32,
     is_invulnerable: bool,
-    audio: GameAudio,
     coins: Vec<Coin>,
     coin_spawn_timer: f32,
     coin_points: i32,
+    combo_multiplier: i32,
+    combo_timer: f32,
+    popups: Vec<PopupText>,
+    particles: ParticleSystem,
+    feedback: Feedback,
+    dying: bool,
+    canvas: Canvas,
+    settings: Settings,
+    window_size_timer: f32,
+    config: GameConfig,
+    config_file: ConfigFile,
+    preset: Preset,
+    level: Level,
+    near_misses: u32,
+    in_near_miss: bool,
+    shadow_gap: f32,
+    rewind: Rewind<Snapshot>,
+    rewinding: bool,
+    swap_cooldown: f32,
+}
+
+// Everything the rewind needs to put the game back the way it was.
+// Lives aren't part of it: rewinding undoes the path, not the hits
+struct Snapshot {
+    camera: GameCamera,
+    player: PlayerMotion,
+    player_pos: Vec2,
+    shadow: ShadowTrail,
+    platforms: Vec<(Vec2, f32)>,  // Position and speed
+    coins: Vec<Coin>,
+    coin_spawn_timer: f32,
+    coin_points: i32,
+    combo_multiplier: i32,
+    combo_timer: f32,
+    invulnerable_timer: f32,
+    is_invulnerable: bool,
+    swap_cooldown: f32,
+    score: f32,
+    run_time: f32,
+    rewind_earned: f32,
 }
 
 impl GameState {
     async fn new() -> Self {
+        let config_file = ConfigFile::new();
+        let preset = Preset::Normal;
+        let config = config_file.load(preset);
+        let settings = Settings::load();
+        let mut canvas = Canvas::new();
+        if settings.integer_scaling {
+            canvas.scale_mode = ScaleMode::Integer;
+        }
+
+        let level = Level::load(DEFAULT_LEVEL);
         let mut world = World::new();
-        let player = Player::new(&mut world).await;
-        let shadow = Shadow::new(SHADOW_FRAMES_DELAY).await;
-        let platforms = create_platforms(&mut world).await;
-        let audio = GameAudio::new().await;
+        let player = Player::new(&mut world, config.physics).await;
+        let shadow = Shadow::new(config.shadow_delay).await;
+        let layout = level.layout.clone().unwrap_or_else(Layout::classic);
+        let platforms = create_platforms(&mut world, &layout, config.platform_speed).await;
 
         Self {
             world,
@@ -133,143 +267,963 @@ impl GameState {
             shadow,
             platforms,
             score: 0.0,
+            run_time: 0.0,
             screen: GameScreen::MainMenu,
-            high_score: 0.0,
-            lives: INITIAL_LIVES,
+            mode: GameMode::Classic,
+            camera: GameCamera::new(),
+            track: RunnerTrack::new(0),
+            seed: 0,
+            seed_override: None,
+            coin_rng: SeededRng::new(0),
+            mutators: Vec::new(),
+            daily_log: DailyLog::load(),
+            daily_date: Date::today(),
+            recording: Replay::new(0, GameMode::Classic, preset, 0),
+            playback: None,
+            saved_replay: None,
+            ghost_replay: None,
+            ghost: None,
+            coop: false,
+            shared_lives: true,
+            partner: None,
+            player_out: false,
+            hunter: None,
+            time_attack: TimeAttack::new(&level, None),
+            best_splits: BestSplits::load(),
+            tick_controls: Controls::default(),
+            tick_accumulator: 0.0,
+            high_scores: HighScores::load(),
+            new_high_score: false,
+            adaptive: AdaptiveDifficulty::new(false),
+            lives: config.lives,
             invulnerable_timer: 0.0,
             is_invulnerable: false,
-            audio,
             coins: Vec::new(),
             coin_spawn_timer: 0.0,
             coin_points: 0,
+            combo_multiplier: 1,
+            combo_timer: 0.0,
+            popups: Vec::new(),
+            particles: ParticleSystem::new(),
+            feedback: Feedback::new(&settings),
+            dying: false,
+            canvas,
+            settings,
+            window_size_timer: 0.0,
+            config,
+            config_file,
+            preset,
+            level,
+            near_misses: 0,
+            in_near_miss: false,
+            shadow_gap: DANGER_RANGE,
+            rewind: Rewind::new(),
+            rewinding: false,
+            swap_cooldown: 0.0,
         }
     }
 
     async fn reset_game(&mut self) {
-        // Update high score before resetting
-        if self.score > self.high_score {
-            self.high_score = self.score;
+        // Re-read the level so edits to its file show up on the next run
+        self.level = Level::load(&self.level.id);
+        self.daily_date = Date::today();
+        self.seed = match (&self.playback, self.mode) {
+            (Some(playback), _) => playback.replay.seed,
+            (None, GameMode::Daily) => self.daily_date.seed(),
+            // Every attempt at a level's time attack gets the same coins
+            (None, GameMode::TimeAttack) => SeededRng::with_stream(0, &self.level.id).next_u64(),
+            // Racing a ghost means playing the run it played
+            (None, _) => self.ghost_replay.as_ref().map(|(_, replay)| replay.seed).or(self.seed_override).unwrap_or_else(random_seed),
+        };
+        self.coin_rng = SeededRng::with_stream(self.seed, "coins");
+
+        // The daily's mutators go on top of a fresh copy of the config, so they never stick around.
+        // It's built from the embedded config, so the date and a replay are all it takes to play a daily again
+        self.config = if self.mode == GameMode::Daily {
+            ConfigFile::embedded(self.preset)
+        } else {
+            self.config_file.load(self.preset)
+        };
+        self.mutators = if self.mode == GameMode::Daily { Mutator::for_seed(self.seed) } else { Vec::new() };
+        for mutator in &self.mutators {
+            mutator.apply(&mut self.config, &mut self.level.sun);
         }
 
         // Reset world and game elements
         self.world = World::new();
-        self.player = Player::new(&mut self.world).await;
-        self.shadow = Shadow::new(25).await;
-        self.platforms = create_platforms(&mut self.world).await;
+        self.player = Player::new(&mut self.world, self.config.physics).await;
+        self.shadow = Shadow::new(self.config.shadow_delay).await;
+        self.hunter = if self.mode == GameMode::Versus {
+            Some(Hunter::new(&mut self.world, &self.config).await)
+        } else {
+            None
+        };
+        self.platforms = match self.mode {
+            GameMode::Classic | GameMode::TimeAttack | GameMode::Versus => {
+                for problem in validate_level(&self.level, &self.config.physics, self.mode.abilities()) {
+                    warn!("level `{}`: {}", self.level.id, problem);
+                }
+                let layout = self.level.layout.clone().unwrap_or_else(Layout::classic);
+                create_platforms(&mut self.world, &layout, self.config.platform_speed).await
+            }
+            GameMode::Generated | GameMode::Daily => {
+                let layout = Layout::generate(self.seed, &self.config.physics, self.mode.abilities());
+                create_platforms(&mut self.world, &layout, self.config.platform_speed).await
+            }
+            GameMode::Climber => create_climber_platforms(&mut self.world, self.config.platform_speed).await,
+            GameMode::Runner => Vec::new(),  // Streamed in from the track
+        };
+        self.camera = GameCamera::new();
+        self.track = RunnerTrack::new(self.seed);
+        self.partner = if self.coop && self.mode.allows_coop() && self.playback.is_none() {
+            let mut partner = Partner::new(&mut self.world, &self.config).await;
+            partner.player.abilities = self.mode.abilities();
+            let spot = self.free_spot(partner.player.collider, PLAYER_TWO_START, spawn_offsets()).unwrap_or(PLAYER_TWO_START);
+            self.world.set_actor_position(partner.player.collider, spot);
+            Some(partner)
+        } else {
+            None
+        };
+        self.player_out = false;
+        self.player.screen_edges = self.mode != GameMode::Runner;
+        self.player.abilities = self.mode.abilities();
         self.score = 0.0;
-        self.lives = INITIAL_LIVES;
+        self.run_time = 0.0;
+        self.new_high_score = false;
+        self.adaptive = AdaptiveDifficulty::new(self.adaptive.enabled);
+        self.lives = self.config.lives;
         self.invulnerable_timer = 0.0;
 
         self.coins.clear();
         self.coin_spawn_timer = 0.0;
         self.coin_points = 0;
+        self.combo_multiplier = 1;
+        self.combo_timer = 0.0;
+        self.popups.clear();
+        self.particles.clear();
+        self.feedback.reset();
+        self.dying = false;
+        self.shadow.wobble = !self.settings.reduce_motion;
+        if let Some(partner) = &mut self.partner {
+            partner.shadow.wobble = !self.settings.reduce_motion;
+        }
+        self.near_misses = 0;
+        self.in_near_miss = false;
+        self.shadow_gap = DANGER_RANGE;
+        self.rewind.clear();
+        self.rewinding = false;
+        self.swap_cooldown = 0.0;
+        self.recording = Replay::new(self.seed, self.mode, self.preset, self.config.fingerprint());
+        self.saved_replay = None;
+        self.time_attack = TimeAttack::new(&self.level, self.best_splits.best(&self.level.id, self.preset));
+        self.tick_controls = Controls::default();
+        self.tick_accumulator = 0.0;
+        self.ghost = match &self.ghost_replay {
+            Some((_, replay)) if self.playback.is_none() => Some(Ghost::new(replay.clone()).await),
+            _ => None,
+        };
+    }
+
+    // Ghosts only make sense on the same course: same mode, and the same day or level where that matters.
+    // The climber's platforms don't come from the seed, and versus replays miss the second player's keys
+    fn can_race(&self, replay: &Replay) -> bool {
+        replay.mode == self.mode
+            && match self.mode {
+                GameMode::Climber | GameMode::Versus => false,
+                GameMode::Daily => replay.seed == Date::today().seed(),
+                GameMode::TimeAttack => replay.label == self.level.id,
+                _ => true,
+            }
+    }
+
+    // The next replay to race against for the selected mode, then none
+    fn next_ghost(&mut self) {
+        let choices: Vec<(String, Replay)> = list_replays().into_iter().filter(|(_, replay)| self.can_race(replay)).collect();
+        let current = self
+            .ghost_replay
+            .as_ref()
+            .and_then(|(path, _)| choices.iter().position(|(choice, _)| choice == path));
+        let next = match current {
+            Some(index) => index + 1,
+            None => 0,
+        };
+        self.ghost_replay = choices.into_iter().nth(next);
     }
 
-    fn handle_shadow_collision(&mut self) {
-        if self.invulnerable_timer <= 0.0 {
+    // Keeps the run that was just played, e.g. as the ghost of a personal best
+    fn save_replay(&mut self, name: &str, label: String) {
+        self.recording.label = label;
+        self.recording.score = self.score;
+        let path = format!("{}/{}.txt", REPLAYS_FOLDER, name);
+        match self.recording.save(&path) {
+            Ok(()) => self.saved_replay = Some(path),
+            Err(e) => warn!("couldn't save the replay to {}: {}", path, e),
+        }
+    }
+
+    // `--replay`: set the game up the way the replay was recorded and watch it
+    async fn start_playback(&mut self, replay: Replay) {
+        self.mode = replay.mode;
+        self.preset = replay.preset;
+        self.adaptive.enabled = false;
+        self.playback = Some(Playback::new(replay));
+        self.reset_game().await;
+
+        // The same controls only make the same run with the same tuning
+        if self.playback.as_ref().is_some_and(|playback| playback.replay.config != self.config.fingerprint()) {
+            warn!("the replay was recorded with different game settings, not playing it");
+            self.playback = None;
+            self.screen = GameScreen::MainMenu;
+            return;
+        }
+        self.screen = GameScreen::Playing;
+    }
+
+    fn game_over(&mut self) {
+        self.screen = GameScreen::GameOver;
+        // Watching a replay doesn't count for anything
+        if self.playback.take().is_some() {
+            self.new_high_score = false;
+            return;
+        }
+
+        // Time attacks only count when they're finished, and co-op and versus runs are just for fun
+        if matches!(self.mode, GameMode::TimeAttack | GameMode::Versus) || self.partner.is_some() {
+            self.new_high_score = false;
+            return;
+        }
+
+        // The daily has its own bests, one per day, and keeps the replay of the best run
+        if self.mode == GameMode::Daily {
+            self.new_high_score = self.daily_log.record(self.daily_date, self.score);
+            if self.new_high_score {
+                let date = self.daily_date.name();
+                self.save_replay(&format!("daily-{}", date), date);
+            }
+            return;
+        }
+
+        // Scores are ranked per difficulty so easy runs don't top hard ones
+        self.new_high_score = self.high_scores.submit(HighScore {
+            score: self.score,
+            preset: self.preset,
+            adaptive: self.adaptive.enabled,
+            mode: self.mode,
+        });
+        // Adaptive runs can't be played back the same way, and the climber isn't seeded
+        if self.new_high_score && !self.adaptive.enabled && self.mode != GameMode::Climber {
+            let name = format!("best-{}-{}", self.mode.name(), self.preset.name()).to_lowercase().replace(' ', "-");
+            self.save_replay(&name, String::new());
+        }
+    }
+
+    fn finish_time_attack(&mut self) {
+        self.screen = GameScreen::Results;
+        if self.playback.take().is_some() {
+            self.new_high_score = false;
+            return;
+        }
+        self.new_high_score = self.best_splits.submit(&self.level.id, self.preset, &self.time_attack.splits);
+        if self.new_high_score {
+            let name = format!("time-attack-{}-{}", self.level.id, self.preset.name()).to_lowercase();
+            self.save_replay(&name, self.level.id.clone());
+        }
+    }
+
+    // Getting caught by the shadow, or running into a cactus in the runner
+    fn handle_hit(&mut self) {
+        if self.invulnerable_timer <= 0.0 && !self.dying {
+            self.lose_life();
+        }
+    }
+
+    // Shared by getting hit and, in the endless modes, falling off the screen
+    fn lose_life(&mut self) {
+        self.lives -= 1;
+        self.adaptive.record_hit();
+        let player_pos = self.world.actor_pos(self.player.collider);
+        self.particles.emit(Effect::Hit, player_pos + PLAYER_SIZE * 0.5);
+        self.feedback.add_trauma(HIT_TRAUMA);
+        // With lives of their own, a co-op player who runs out sits out while the other plays on
+        if self.lives <= 0 && self.partner.as_ref().is_some_and(|partner| !partner.out && !self.shared_lives) {
+            self.player_out = true;
+        } else if self.lives <= 0 {
+            // Let the last hit play out in slow motion before the game over screen
+            self.dying = true;
+            self.feedback.slow_motion(FINAL_SLOW_MOTION_DURATION);
+        } else {
+            self.feedback.hit_stop();
+            // Start invulnerability period
+            self.invulnerable_timer = self.config.invulnerability_duration;
+            // Optional: Reset player position after hit
+            let new_pos = self.world.actor_pos(self.player.collider);
+            // new_pos.y -= 50.0; // Move player up a bit to avoid immediate re-collision
+            self.world.set_actor_position(self.player.collider, new_pos);
+        }
+    }
+
+    // The second player got caught by their shadow
+    fn partner_hit(&mut self) {
+        let Some(partner) = &mut self.partner else {
+            return;
+        };
+        self.adaptive.record_hit();
+        let partner_pos = self.world.actor_pos(partner.player.collider);
+        self.particles.emit(Effect::Hit, partner_pos + PLAYER_SIZE * 0.5);
+        self.feedback.add_trauma(HIT_TRAUMA);
+
+        let lives_left = if self.shared_lives {
             self.lives -= 1;
-            if self.lives <= 0 {
-                self.screen = GameScreen::GameOver;
-            } else {
-                // Start invulnerability period
-                self.invulnerable_timer = INVULNERABILITY_DURATION;
-                // Optional: Reset player position after hit
-                let mut new_pos = self.world.actor_pos(self.player.collider);
-                // new_pos.y -= 50.0; // Move player up a bit to avoid immediate re-collision
-                self.world.set_actor_position(self.player.collider, new_pos);
+            self.lives
+        } else {
+            partner.lives -= 1;
+            partner.lives
+        };
+        if lives_left > 0 {
+            self.feedback.hit_stop();
+            partner.invulnerable_timer = self.config.invulnerability_duration;
+        } else if self.shared_lives || self.player_out {
+            self.dying = true;
+            self.feedback.slow_motion(FINAL_SLOW_MOTION_DURATION);
+        } else {
+            partner.out = true;
+        }
+    }
+
+    // Re-read the config file for the current preset and push it into the live game
+    fn apply_config(&mut self) {
+        self.config = self.config_file.load(self.preset);
+        self.player.physics = self.config.physics;
+        if let Some(partner) = &mut self.partner {
+            partner.player.physics = self.config.physics;
+        }
+        if let Some(hunter) = &mut self.hunter {
+            hunter.apply_config(&self.config);
+        }
+        for platform in self.platforms.iter_mut() {
+            platform.set_speed(self.config.platform_speed);
+        }
+        self.shadow.set_delay(self.adaptive.shadow_delay(self.config.shadow_delay));
+    }
+
+    fn update_window(&mut self) {
+        let alt_down = is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt);
+        if alt_down && is_key_pressed(KeyCode::Enter) {
+            self.settings.fullscreen = !self.settings.fullscreen;
+            set_fullscreen(self.settings.fullscreen);
+            self.settings.save();
+        }
+
+        if is_key_pressed(KeyCode::F2) {
+            self.canvas.scale_mode = match self.canvas.scale_mode {
+                ScaleMode::Fit => ScaleMode::Integer,
+                ScaleMode::Integer => ScaleMode::Fit,
+            };
+            self.settings.integer_scaling = self.canvas.scale_mode == ScaleMode::Integer;
+            self.settings.save();
+        }
+
+        // Remember the windowed size for next time
+        let (width, height) = (screen_width() as i32, screen_height() as i32);
+        if !self.settings.fullscreen && (width != self.settings.window_width || height != self.settings.window_height) {
+            self.window_size_timer += get_frame_time();
+            if self.window_size_timer > WINDOW_SIZE_SAVE_DELAY {
+                self.settings.window_width = width;
+                self.settings.window_height = height;
+                self.settings.save();
+                self.window_size_timer = 0.0;
             }
+        } else {
+            self.window_size_timer = 0.0;
         }
     }
 
     async fn update(&mut self) {
+        // A reload would drop the daily's mutators, and a replay has to play on the config it started with
+        if self.config_file.poll() && self.mode != GameMode::Daily && self.playback.is_none() {
+            self.apply_config();
+        }
+
+        self.update_window();
+
         match self.screen {
-            GameScreen::Playing => self.update_playing(),
+            GameScreen::Playing => {
+                let dt = self.feedback.update(get_frame_time());
+                if let Some(playback) = &mut self.playback {
+                    // A replay goes at the pace it was recorded at, hit-stops and slow motion included
+                    match playback.next_frame() {
+                        Some(frame) => {
+                            let position = self.world.actor_pos(self.player.collider);
+                            playback.check_sync(&frame, position);
+                            self.update_playing(frame.dt, frame.controls);
+                        }
+                        None => self.game_over(),
+                    }
+                } else if self.mode == GameMode::TimeAttack {
+                    self.update_ticks(dt);
+                } else if dt > 0.0 {
+                    let bindings = if self.partner.is_some() || self.hunter.is_some() { &PLAYER_ONE } else { &SOLO };
+                    self.update_playing(dt, Controls::read(bindings));
+                }
+            }
             GameScreen::Paused => self.update_paused(),
             GameScreen::MainMenu => self.update_main_menu().await,
-            GameScreen::GameOver => self.update_game_over().await,
+            GameScreen::GameOver | GameScreen::Results => self.update_game_over().await,
+        }
+    }
+
+    // Time attack runs on fixed ticks, so its clock doesn't depend on the frame rate
+    fn update_ticks(&mut self, dt: f32) {
+        self.tick_controls.latch(Controls::read(&SOLO));
+        self.tick_accumulator = (self.tick_accumulator + dt).min(TICK * MAX_TICKS_PER_FRAME as f32);
+        while self.tick_accumulator >= TICK && self.screen == GameScreen::Playing {
+            self.tick_accumulator -= TICK;
+            let controls = self.tick_controls.take();
+            self.update_playing(TICK, controls);
         }
     }
 
     fn spawn_coin(&mut self) {
-        // Random position within window bounds
-        let x = gen_range(0.0, WINDOW_WIDTH - COIN_SIZE.x);
-        let y = gen_range(100.0, WINDOW_HEIGHT - COIN_SIZE.y - 50.0);  // Keep above ground level
+        // Random position within one of the level's coin zones, or anywhere within the view
+        let view = self.camera.view();
+        let zones = if self.mode.uses_level() { &self.level.coin_zones[..] } else { &[] };
+        let rng = &mut self.coin_rng;
+        let (x, y) = if zones.is_empty() {
+            (
+                rng.gen_range(view.left(), view.right() - COIN_SIZE.x),
+                rng.gen_range(view.top() + 100.0, view.bottom() - COIN_SIZE.y - 50.0),  // Keep above ground level
+            )
+        } else {
+            let zone = zones[rng.gen_index(zones.len())];
+            (
+                rng.gen_range(zone.left(), (zone.right() - COIN_SIZE.x).max(zone.left())),
+                rng.gen_range(zone.top(), (zone.bottom() - COIN_SIZE.y).max(zone.top())),
+            )
+        };
 
         // Spawn the coin
         block_on(async {
-            let coin = Coin::new(vec2(x, y)).await;
+            let tier = CoinTier::random(&self.config, &mut self.coin_rng);
+            let coin = Coin::new(vec2(x, y), tier, tier.stats(&self.config).lifetime).await;
             self.coins.push(coin);
         });
     }
 
-    fn update_playing(&mut self) {
-        // fixme https://github.com/not-fl3/macroquad/issues/440 ???
-        // self.audio.play_background();
+    // Returns the points, for whichever player picked the coin up
+    fn collect_coin(&mut self, coin: &Coin) -> i32 {
+        let points = coin.tier.stats(&self.config).points * self.combo_multiplier;
+        if self.mode == GameMode::TimeAttack {
+            self.time_attack.collect_coin();
+        }
+        self.rewind.add_charge(points);
+        self.particles.emit(Effect::Sparkle, coin.position + COIN_SIZE * 0.5);
 
-        // Update coin spawn timer
-        self.coin_spawn_timer -= get_frame_time();
-        if self.coin_spawn_timer <= 0.0 {
-            self.spawn_coin();
-            self.coin_spawn_timer = COIN_SPAWN_INTERVAL;
+        let text = if self.combo_multiplier > 1 {
+            format!("+{} x{}", points, self.combo_multiplier)
+        } else {
+            format!("+{}", points)
+        };
+        self.popups.push(PopupText::new(text, coin.position, coin.tier.text_color()));
+
+        // Every pickup inside the combo window raises the multiplier
+        self.combo_multiplier = (self.combo_multiplier + 1).min(COMBO_MAX_MULTIPLIER);
+        self.combo_timer = COMBO_WINDOW;
+        points
+    }
+
+    fn update_combo(&mut self, dt: f32) {
+        if self.combo_multiplier > 1 {
+            self.combo_timer -= dt;
+            if self.combo_timer <= 0.0 {
+                // Decay one step at a time instead of dropping straight back to x1
+                self.combo_multiplier -= 1;
+                self.combo_timer = if self.combo_multiplier > 1 { COMBO_WINDOW } else { 0.0 };
+            }
+        }
+
+        self.popups.retain_mut(|popup| popup.update(dt));
+    }
+
+    fn update_near_miss(&mut self, player_pos: Vec2, hit: bool) {
+        let gap = match &self.hunter {
+            Some(hunter) => self.shadow.is_solid().then(|| hunter.gap_to(&self.world, player_pos)),
+            None => self.shadow.gap_to_player(player_pos),
+        };
+        self.shadow_gap = gap.unwrap_or(DANGER_RANGE);
+
+        if hit || self.is_invulnerable {
+            // Getting caught (or being untouchable) doesn't count as a close call
+            self.in_near_miss = false;
+            return;
         }
 
-        // Update existing coins
+        if self.shadow_gap < NEAR_MISS_DISTANCE {
+            self.in_near_miss = true;
+        } else if self.in_near_miss {
+            // The shadow got close and the player pulled away: reward the risk
+            self.in_near_miss = false;
+            self.near_misses += 1;
+            self.feedback.add_trauma(NEAR_MISS_TRAUMA);
+            self.adaptive.record_near_miss();
+            self.score += NEAR_MISS_POINTS;
+            self.popups.push(PopupText::new(
+                format!("Near miss! +{:.0}", NEAR_MISS_POINTS),
+                player_pos,
+                TEXT_WARNING,
+            ));
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            camera: self.camera,
+            player: self.player.motion(),
+            player_pos: self.world.actor_pos(self.player.collider),
+            shadow: self.shadow.trail(),
+            platforms: self
+                .platforms
+                .iter()
+                .map(|platform| (self.world.solid_pos(platform.collider), platform.speed))
+                .collect(),
+            coins: self.coins.clone(),
+            coin_spawn_timer: self.coin_spawn_timer,
+            coin_points: self.coin_points,
+            combo_multiplier: self.combo_multiplier,
+            combo_timer: self.combo_timer,
+            invulnerable_timer: self.invulnerable_timer,
+            is_invulnerable: self.is_invulnerable,
+            swap_cooldown: self.swap_cooldown,
+            score: self.score,
+            run_time: self.run_time,
+            rewind_earned: self.rewind.earned(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        for (platform, (pos, speed)) in self.platforms.iter_mut().zip(snapshot.platforms) {
+            platform.move_to(&mut self.world, pos);
+            platform.speed = speed;
+        }
+        // After the platforms, which may have carried the player along
+        self.camera = snapshot.camera;
+        self.player.set_motion(snapshot.player);
+        self.world.set_actor_position(self.player.collider, snapshot.player_pos);
+        self.shadow.set_trail(snapshot.shadow);
+        self.coins = snapshot.coins;
+        self.coin_spawn_timer = snapshot.coin_spawn_timer;
+        self.coin_points = snapshot.coin_points;
+        self.combo_multiplier = snapshot.combo_multiplier;
+        self.combo_timer = snapshot.combo_timer;
+        // Lives lost stay lost, and so does the invulnerability that came with them, or the same hit could land twice
+        self.invulnerable_timer = self.invulnerable_timer.max(snapshot.invulnerable_timer);
+        self.is_invulnerable |= snapshot.is_invulnerable;
+        self.swap_cooldown = snapshot.swap_cooldown;
+        self.score = snapshot.score;
+        self.run_time = snapshot.run_time;
+        self.rewind.take_back_since(snapshot.rewind_earned);
+    }
+
+    // Trade places with the shadow, which then walks the trail back towards the player
+    fn swap_with_shadow(&mut self) {
+        let Some(shadow_pos) = self.shadow.position().filter(|_| self.shadow.is_solid()) else {
+            return;
+        };
         let player_pos = self.world.actor_pos(self.player.collider);
+        let Some(target) = self.free_spot(self.player.collider, shadow_pos, SWAP_NUDGES) else {
+            self.popups.push(PopupText::new("Blocked".to_string(), player_pos, TEXT_WARNING));
+            return;
+        };
+
+        self.world.set_actor_position(self.player.collider, target);
+        self.player.speed = Vec2::ZERO;
+        self.shadow.reverse_trail();
+        self.swap_cooldown = SWAP_COOLDOWN;
+        self.particles.emit(Effect::ShadowSmoke, player_pos + PLAYER_SIZE * 0.5);
+        self.particles.emit(Effect::Dust, target + PLAYER_SIZE * 0.5);
+    }
+
+    // The first of `offsets` from `pos` where the actor fits, never outside the view
+    fn free_spot(&self, actor: Actor, pos: Vec2, offsets: impl IntoIterator<Item = Vec2>) -> Option<Vec2> {
+        let view = self.camera.view();
+        offsets
+            .into_iter()
+            .map(|offset| {
+                let spot = pos + offset;
+                vec2(
+                    spot.x.clamp(view.left(), view.right() - PLAYER_SIZE.x),
+                    spot.y.clamp(view.top(), view.bottom() - PLAYER_SIZE.y),
+                )
+            })
+            .find(|spot| !self.world.collide_check(actor, *spot))
+    }
+
+    // Scroll the view, recycle the platforms left behind and score the height reached
+    fn update_climber(&mut self, player_pos: Vec2, dt: f32) {
+        let height = (GROUND_Y - (player_pos.y + PLAYER_SIZE.y)).max(0.0);
+        let scroll_speed = (CLIMB_SCROLL_SPEED + height * CLIMB_SCROLL_RAMP).min(CLIMB_MAX_SCROLL_SPEED);
+        self.camera.climb(player_pos.y, scroll_speed, dt);
+        let view = self.camera.view();
+
+        // Platforms that dropped below the view come back above the highest one.
+        // The ground is left where it is
+        let mut top = self
+            .platforms
+            .iter()
+            .map(|platform| self.world.solid_pos(platform.collider).y)
+            .fold(f32::MAX, f32::min);
+        for platform in self.platforms.iter_mut() {
+            let pos = self.world.solid_pos(platform.collider);
+            if pos.y > view.bottom() + CLIMB_CULL_MARGIN && platform.size.x < WINDOW_WIDTH {
+                top -= gen_range(CLIMB_GAP_MIN, CLIMB_GAP_MAX);
+                let x = climber_platform_x(platform.size.x, platform.speed);
+                platform.move_to(&mut self.world, vec2(x, top));
+            }
+        }
+
+        self.score = self.score.max(height / PIXELS_PER_METER);
+
+        if player_pos.y > view.bottom() && !self.dying {
+            self.fall_off();
+        }
+    }
+
+    // Dropping out of the view (or getting crushed by it) costs a life
+    // and puts the player back on the platform nearest the middle
+    fn fall_off(&mut self) {
+        self.lose_life();
+        if self.dying {
+            return;
+        }
+
+        // Only the visible part of each platform counts
+        let view = self.camera.view();
+        let landing = self
+            .platforms
+            .iter()
+            .filter_map(|platform| {
+                let pos = self.world.solid_pos(platform.collider);
+                Rect::new(pos.x, pos.y, platform.size.x, platform.size.y).intersect(view)
+            })
+            .filter(|visible| visible.w >= PLAYER_SIZE.x)
+            .min_by(|a, b| {
+                let distance = |rect: &Rect| rect.center().distance(view.center());
+                distance(a).total_cmp(&distance(b))
+            });
+        if let Some(visible) = landing {
+            let spot = vec2(visible.center().x - PLAYER_SIZE.x * 0.5, visible.y - PLAYER_SIZE.y - 1.0);
+            self.world.set_actor_position(self.player.collider, spot);
+        }
+        self.player.speed = Vec2::ZERO;
+        self.is_invulnerable = true;
+    }
+
+    // Scroll sideways, build the track ahead and clear what's been left behind
+    fn update_runner(&mut self, player_pos: Vec2, dt: f32) {
+        let distance = player_pos.x.max(0.0);
+        let scroll_speed = (RUN_SCROLL_SPEED + distance * RUN_SCROLL_RAMP).min(RUN_MAX_SCROLL_SPEED);
+        self.camera.position.x += scroll_speed * dt;
+        let view = self.camera.view();
+
+        for chunk in self.track.extend_to(view.right() + RUN_LOOKAHEAD) {
+            self.place_chunk(&chunk);
+            // The chunk may have taken platforms the history still has somewhere else
+            self.rewind.forget_history();
+        }
+        self.coins.retain(|coin| coin.position.x > view.left() - RUN_CULL_MARGIN);
+
+        self.score = self.score.max(distance / PIXELS_PER_METER);
+
+        // Running into a cactus hurts
+        let player_rect = Rect::new(player_pos.x, player_pos.y, PLAYER_SIZE.x, PLAYER_SIZE.y);
+        let hit_cactus = self
+            .platforms
+            .iter()
+            .flat_map(|platform| platform.cactus_hitboxes(&self.world))
+            .any(|cactus| cactus.overlaps(&player_rect));
+        if hit_cactus {
+            self.is_invulnerable = true;
+            self.handle_hit();
+        }
+
+        if player_pos.y > view.bottom() && !self.dying {
+            self.fall_off();
+        }
+    }
+
+    fn place_chunk(&mut self, chunk: &Chunk) {
+        let view = self.camera.view();
+        for placed in &chunk.platforms {
+            let size = vec2(placed.width, PLATFORM_SIZE.y);
+            // Reuse a platform of the same width that's long gone off the left, the world can't remove any
+            let reusable = self.platforms.iter().position(|platform| {
+                platform.size == size && self.world.solid_pos(platform.collider).x + size.x < view.left() - RUN_CULL_MARGIN
+            });
+            let platform = match reusable {
+                Some(index) => {
+                    let platform = &mut self.platforms[index];
+                    platform.move_to(&mut self.world, placed.pos);
+                    platform.speed = placed.speed;
+                    platform.cacti = placed.cacti.clone();
+                    platform
+                }
+                None => {
+                    let platform = block_on(Platform::new(&mut self.world, placed.pos, size, placed.speed, placed.cacti.clone()));
+                    self.platforms.push(platform);
+                    self.platforms.last_mut().unwrap()
+                }
+            };
+            platform.range = (placed.pos.x, placed.pos.x + placed.travel);
+        }
+
+        for coin in &chunk.coins {
+            let tier = CoinTier::from_name(&coin.tier).unwrap_or(CoinTier::Common);
+            self.coins.push(block_on(Coin::new(coin.pos, tier, RUN_COIN_LIFETIME)));
+        }
+    }
+
+    fn update_playing(&mut self, dt: f32, controls: Controls) {
+        // Check for pause first, so a paused frame doesn't move the game on at all
+        if is_key_pressed(KeyCode::Escape) {
+            self.screen = GameScreen::Paused;
+            return;
+        }
+
+        let position = self.world.actor_pos(self.player.collider);
+        self.recording.frames.push(ReplayFrame { dt, controls, position });
+        if let Some(ghost) = &mut self.ghost {
+            ghost.advance(dt);
+        }
+
+        // Holding R plays the history backwards instead of moving the game on
+        self.rewinding = controls.rewind_held && !self.dying && self.mode.allows_rewind() && self.rewind.can_rewind();
+        if self.rewinding {
+            match self.rewind.step_back() {
+                Some(snapshot) if snapshot.platforms.len() == self.platforms.len() => self.restore(snapshot),
+                // Platforms were added since, there's no putting them back
+                Some(_) => self.rewind.forget_history(),
+                None => {}
+            }
+            self.particles.update(dt);
+            return;
+        }
+        if !self.dying && self.mode.allows_rewind() {
+            let snapshot = self.snapshot();
+            self.rewind.record(dt, snapshot);
+        }
+
+        // Update coin spawn timer. The runner's coins come with the track
+        self.coin_spawn_timer -= dt;
+        if self.coin_spawn_timer <= 0.0 && self.mode != GameMode::Runner {
+            self.spawn_coin();
+            self.coin_spawn_timer = self.adaptive.coin_spawn_interval(self.config.coin_spawn_interval);
+        }
+
+        // Update existing coins, either player can pick them up
+        let player_pos = (!self.player_out).then(|| self.world.actor_pos(self.player.collider));
+        let partner_pos = self
+            .partner
+            .as_ref()
+            .filter(|partner| !partner.out)
+            .map(|partner| self.world.actor_pos(partner.player.collider));
         let mut i = 0;
         while i < self.coins.len() {
-            if !self.coins[i].update() {
-                self.coins.remove(i);
-            } else if self.coins[i].collides_with_player(player_pos, PLAYER_SIZE) {
-                self.coin_points += COIN_POINTS;
+            if !self.coins[i].update(dt) {
                 self.coins.remove(i);
+                continue;
+            }
+            let coin = &self.coins[i];
+            let touches = |pos: Option<Vec2>| pos.is_some_and(|pos| coin.collides_with_player(pos, PLAYER_SIZE));
+            if touches(player_pos) {
+                let coin = self.coins.remove(i);
+                self.coin_points += self.collect_coin(&coin);
+            } else if touches(partner_pos) {
+                let coin = self.coins.remove(i);
+                let points = self.collect_coin(&coin);
+                if let Some(partner) = &mut self.partner {
+                    partner.coin_points += points;
+                }
             } else {
                 i += 1;
             }
         }
+        self.update_combo(dt);
 
         // Update invulnerability
         if self.is_invulnerable {
-            self.invulnerable_timer -= get_frame_time();
+            self.invulnerable_timer -= dt;
             if self.invulnerable_timer <= 0.0 {
                 self.is_invulnerable = false;
                 self.invulnerable_timer = 0.0;
             }
         }
 
-        // Check for pause
-        if is_key_pressed(KeyCode::Escape) {
-            self.screen = GameScreen::Paused;
-            return;
+        self.swap_cooldown = (self.swap_cooldown - dt).max(0.0);
+        if controls.swap_pressed && self.swap_cooldown <= 0.0 && !self.dying {
+            self.swap_with_shadow();
         }
 
         // Update game elements
         for platform in self.platforms.iter_mut() {
-            platform.update(&mut self.world);
+            platform.update(&mut self.world, dt);
         }
 
-        self.player.update(&mut self.world);
+        if !self.player_out {
+            self.player.update(&mut self.world, controls, dt);
+        }
 
-        // Enforce window boundaries
+        // Keep the player inside the view
+        let view = self.camera.view();
         let mut player_pos = self.world.actor_pos(self.player.collider);
-        if player_pos.x < 0.0 {
-            player_pos.x = 0.0;
+        if player_pos.x < view.left() && self.mode == GameMode::Runner {
+            // The runner's view pushes the player along, squashing them against a wall costs a life
+            let pushed = self.world.move_h(self.player.collider, view.left() - player_pos.x);
+            if !pushed && !self.dying {
+                self.fall_off();
+            }
+            player_pos = self.world.actor_pos(self.player.collider);
+        } else if player_pos.x < view.left() {
+            player_pos.x = view.left();
             self.world.set_actor_position(self.player.collider, player_pos);
             self.player.speed.x = 0.0;
-        } else if player_pos.x > WINDOW_WIDTH - PLAYER_SIZE.x {
-            player_pos.x = WINDOW_WIDTH - PLAYER_SIZE.x;
+        } else if player_pos.x > view.right() - PLAYER_SIZE.x {
+            player_pos.x = view.right() - PLAYER_SIZE.x;
             self.world.set_actor_position(self.player.collider, player_pos);
             self.player.speed.x = 0.0;
         }
 
-        self.shadow.update(player_pos);
+        match self.mode {
+            GameMode::Classic | GameMode::Generated | GameMode::Daily | GameMode::TimeAttack | GameMode::Versus => {}
+            GameMode::Climber => self.update_climber(player_pos, dt),
+            GameMode::Runner => self.update_runner(player_pos, dt),
+        }
+        player_pos = self.world.actor_pos(self.player.collider);
 
-        // Check for collision with shadow
-        if self.shadow.collides_with_player(player_pos) {
-            self.is_invulnerable = true;
-            self.handle_shadow_collision();
+        // Dust puffs at the player's feet
+        if !self.player_out && (self.player.just_jumped || self.player.just_landed) {
+            self.particles.emit(Effect::Dust, player_pos + vec2(PLAYER_SIZE.x * 0.5, PLAYER_SIZE.y));
+        }
+
+        // Low sun: long shadow, pushed aside and further back. Noon: short and close. Night: faint
+        let time_of_day = self.level.sun.time_of_day(self.run_time);
+        let sun = sun_height(time_of_day);
+        self.shadow.opacity = SHADOW_NIGHT_OPACITY + (1.0 - SHADOW_NIGHT_OPACITY) * sun.max(0.0);
+        self.shadow.afterimage_spacing =
+            AFTERIMAGE_SPACING + (self.level.sun.stretch(time_of_day) * AFTERIMAGE_EXTRA_SPACING) as usize;
+        self.shadow.set_offset(self.level.sun.shadow_offset(time_of_day));
+
+        let player_rect = Rect::new(player_pos.x, player_pos.y, PLAYER_SIZE.x, PLAYER_SIZE.y);
+        if !self.player_out {
+            let in_shade = self.mode.uses_level() && self.level.in_shade(player_rect);
+            self.shadow.update(player_pos, self.player.state, in_shade, dt);
+            self.update_hunter(dt);
+            let smoke_pos = match &self.hunter {
+                Some(hunter) => Some(self.world.actor_pos(hunter.body.collider)),
+                None => self.shadow.position(),
+            };
+            if let Some(smoke_pos) = smoke_pos.filter(|_| self.shadow.is_solid()) {
+                self.particles.emit_trail(Effect::ShadowSmoke, smoke_pos + PLAYER_SIZE * 0.5, dt);
+            }
+
+            // Check for collision with shadow. In versus the trail only holds the leash, the steered shadow tags
+            let hit = match &self.hunter {
+                Some(hunter) => self.shadow.is_solid() && hunter.tags(&self.world, player_pos),
+                None => self.shadow.collides_with_player(player_pos),
+            };
+            if hit {
+                self.is_invulnerable = true;
+                self.handle_hit();
+            }
+            self.update_near_miss(player_pos, hit);
+        }
+        self.update_partner(dt, time_of_day);
+        self.particles.update(dt);
+
+        // Adaptive difficulty only ever touches the shadow delay and coin rate, the sun scales the result
+        self.adaptive.update(dt);
+        let delay = self.level.sun.shadow_delay(self.adaptive.shadow_delay(self.config.shadow_delay), time_of_day);
+        self.shadow.set_delay(delay);
+        if let Some(partner) = &mut self.partner {
+            partner.shadow.set_delay(delay);
+        }
+
+        if self.dying {
+            if !self.feedback.is_slow_motion() {
+                self.game_over();
+            }
+        } else {
+            // The endless modes score height or distance instead
+            if !matches!(self.mode, GameMode::Climber | GameMode::Runner) && !self.player_out {
+                self.score += dt;
+            }
+            self.run_time += dt;
+
+            if self.mode == GameMode::TimeAttack && self.time_attack.tick(player_rect) {
+                self.finish_time_attack();
+            }
+
+            // The runner wins a versus by outlasting the timer
+            if self.mode == GameMode::Versus && self.run_time >= self.config.versus_time {
+                self.game_over();
+            }
+        }
+    }
+
+    // Player two's shadow in versus, kept within the view like the runner
+    fn update_hunter(&mut self, dt: f32) {
+        let Some(hunter) = &mut self.hunter else {
+            return;
+        };
+        if self.dying {
+            return;
+        }
+
+        hunter.update(&mut self.world, Controls::read(&PLAYER_TWO), self.shadow.position(), dt);
+        let view = self.camera.view();
+        let mut pos = self.world.actor_pos(hunter.body.collider);
+        if pos.x < view.left() || pos.x > view.right() - PLAYER_SIZE.x {
+            pos.x = pos.x.clamp(view.left(), view.right() - PLAYER_SIZE.x);
+            self.world.set_actor_position(hunter.body.collider, pos);
+            hunter.body.speed.x = 0.0;
+        }
+    }
+
+    // The second player of a co-op run: same rules, their own keys, shadow and score
+    fn update_partner(&mut self, dt: f32, time_of_day: f32) {
+        let Some(partner) = &mut self.partner else {
+            return;
+        };
+        if partner.out || self.dying {
+            return;
+        }
+
+        partner.player.update(&mut self.world, Controls::read(&PLAYER_TWO), dt);
+        let view = self.camera.view();
+        let mut pos = self.world.actor_pos(partner.player.collider);
+        if pos.x < view.left() || pos.x > view.right() - PLAYER_SIZE.x {
+            pos.x = pos.x.clamp(view.left(), view.right() - PLAYER_SIZE.x);
+            self.world.set_actor_position(partner.player.collider, pos);
+            partner.player.speed.x = 0.0;
+        }
+        if partner.player.just_jumped || partner.player.just_landed {
+            self.particles.emit(Effect::Dust, pos + vec2(PLAYER_SIZE.x * 0.5, PLAYER_SIZE.y));
+        }
+
+        // The sun bends both shadows the same way
+        partner.shadow.opacity = self.shadow.opacity;
+        partner.shadow.afterimage_spacing = self.shadow.afterimage_spacing;
+        partner.shadow.set_offset(self.level.sun.shadow_offset(time_of_day));
+        let rect = Rect::new(pos.x, pos.y, PLAYER_SIZE.x, PLAYER_SIZE.y);
+        let in_shade = self.mode.uses_level() && self.level.in_shade(rect);
+        partner.shadow.update(pos, partner.player.state, in_shade, dt);
+        if let Some(shadow_pos) = partner.shadow.position().filter(|_| partner.shadow.is_solid()) {
+            self.particles.emit_trail(Effect::ShadowSmoke, shadow_pos + PLAYER_SIZE * 0.5, dt);
         }
 
-        self.score += get_frame_time();
+        partner.score += dt;
+        partner.invulnerable_timer = (partner.invulnerable_timer - dt).max(0.0);
+        if partner.invulnerable_timer <= 0.0 && partner.shadow.collides_with_player(pos) {
+            self.partner_hit();
+        }
     }
 
     fn update_paused(&mut self) {
@@ -279,6 +1233,61 @@ impl GameState {
     }
 
     async fn update_main_menu(&mut self) {
+        if is_key_pressed(KeyCode::Up) {
+            self.mode = self.mode.previous();
+        } else if is_key_pressed(KeyCode::Down) {
+            self.mode = self.mode.next();
+        }
+
+        // A ghost picked for another mode doesn't come along
+        if self.ghost_replay.as_ref().is_some_and(|(_, replay)| !self.can_race(replay)) {
+            self.ghost_replay = None;
+        }
+        if is_key_pressed(KeyCode::G) {
+            self.next_ghost();
+        }
+
+        // Everyone plays the daily on the same terms
+        let daily = self.mode == GameMode::Daily;
+        if daily && (self.preset != Preset::Normal || self.adaptive.enabled) {
+            self.preset = Preset::Normal;
+            self.adaptive.enabled = false;
+            self.apply_config();
+        }
+
+        if is_key_pressed(KeyCode::Left) && !daily {
+            self.preset = self.preset.previous();
+            self.apply_config();
+        } else if is_key_pressed(KeyCode::Right) && !daily {
+            self.preset = self.preset.next();
+            self.apply_config();
+        }
+        if is_key_pressed(KeyCode::A) && !daily {
+            self.adaptive.enabled = !self.adaptive.enabled;
+        }
+        if is_key_pressed(KeyCode::C) {
+            self.coop = !self.coop;
+        }
+        if is_key_pressed(KeyCode::L) {
+            self.shared_lives = !self.shared_lives;
+        }
+        if is_key_pressed(KeyCode::M) {
+            self.settings.reduce_motion = !self.settings.reduce_motion;
+            self.feedback.apply_settings(&self.settings);
+            self.shadow.wobble = !self.settings.reduce_motion;
+            self.settings.save();
+        }
+
+        // Clicking the left or right half of the difficulty label cycles it too
+        if is_mouse_button_pressed(MouseButton::Left) && !daily {
+            let mouse = self.canvas.mouse_position();
+            let label_y = WINDOW_HEIGHT * 0.55;
+            if mouse.y > label_y - 25.0 && mouse.y < label_y + 5.0 {
+                self.preset = if mouse.x < WINDOW_WIDTH * 0.5 { self.preset.previous() } else { self.preset.next() };
+                self.apply_config();
+            }
+        }
+
         if is_key_pressed(KeyCode::Space) {
             self.reset_game().await;
             self.screen = GameScreen::Playing;
@@ -295,14 +1304,23 @@ impl GameState {
     }
 
     fn draw(&mut self) {
-        clear_background(BACKGROUND_COLOR);
+        // Everything below is drawn in game space, whatever the window size
+        self.canvas.begin();
+        let parallax_x = match self.screen {
+            GameScreen::MainMenu => 0.0,
+            _ => self.world.actor_pos(self.player.collider).x - WINDOW_WIDTH * 0.5,
+        };
+        draw_background(self.level.sun.time_of_day(self.run_time), parallax_x);
 
         match self.screen {
             GameScreen::Playing => self.draw_playing(),
             GameScreen::Paused => self.draw_paused(),
             GameScreen::MainMenu => self.draw_main_menu(),
             GameScreen::GameOver => self.draw_game_over(),
+            GameScreen::Results => self.draw_results(),
         }
+
+        self.canvas.present();
     }
 
 
@@ -315,23 +1333,65 @@ impl GameState {
     }
 
     fn draw_playing(&mut self) {
+        // Platforms and the HUD share the background's light, so night falls on them too
+        let light = Palette::at(self.level.sun.time_of_day(self.run_time)).light;
+
+        // The world shakes, the HUD stays put
+        self.canvas.set_view(self.camera.position - self.feedback.shake_offset());
+
+        if self.mode.uses_level() {
+            self.level.draw_shade();
+        }
+        if self.mode == GameMode::TimeAttack {
+            self.time_attack.draw_flags();
+        }
+
         // Draw coins
         for coin in &self.coins {
             coin.draw();
         }
+        for popup in &self.popups {
+            popup.draw();
+        }
 
         // Draw game elements
         for platform in &self.platforms {
-            platform.draw(&self.world);
+            platform.draw(&self.world, light);
+        }
+        self.particles.draw();
+        let player_pos = self.world.actor_pos(self.player.collider);
+        match &mut self.hunter {
+            Some(hunter) => {
+                let alpha = self.shadow.opacity * self.shadow.visibility;
+                hunter.draw(&self.world, self.shadow.position(), alpha);
+            }
+            None if !self.player_out => self.shadow.draw(player_pos),
+            None => {}
+        }
+        if let Some(ghost) = &mut self.ghost {
+            ghost.draw();
+        }
+
+        if let Some(partner) = self.partner.as_mut().filter(|partner| !partner.out) {
+            let partner_pos = self.world.actor_pos(partner.player.collider);
+            partner.shadow.draw(partner_pos);
+            if partner.invulnerable_timer <= 0.0 || (partner.invulnerable_timer * FLASH_FREQUENCY).sin() > 0.0 {
+                partner.player.draw(&self.world);
+            }
         }
-        self.shadow.draw();
 
         // Draw player with flashing effect when invulnerable
-        if self.should_draw_player() {
+        if self.should_draw_player() && !self.player_out {
             self.player.draw(&self.world);
         }
 
-        self.draw_ui();
+        self.canvas.set_view(Vec2::ZERO);
+
+        if self.rewinding {
+            draw_rectangle(0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT, REWIND_TINT);
+        }
+
+        self.draw_ui(light);
     }
 
     fn draw_paused(&mut self) {
@@ -339,8 +1399,8 @@ impl GameState {
         self.draw_playing();
 
         // Draw pause overlay
-        let screen_w = screen_width();
-        let screen_h = screen_height();
+        let screen_w = WINDOW_WIDTH;
+        let screen_h = WINDOW_HEIGHT;
 
         // Semi-transparent overlay
         draw_rectangle(0.0, 0.0, screen_w, screen_h, Color::new(0.0, 0.0, 0.0, 0.9));
@@ -368,8 +1428,8 @@ impl GameState {
     }
 
     fn draw_main_menu(&self) {
-        let screen_w = screen_width();
-        let screen_h = screen_height();
+        let screen_w = WINDOW_WIDTH;
+        let screen_h = WINDOW_HEIGHT;
 
         // Title
         let title_text = "CHA(SE)DOW";
@@ -377,21 +1437,104 @@ impl GameState {
         draw_text(
             title_text,
             screen_w * 0.5 - title_dims.width * 0.5,
-            screen_h * 0.4,
+            screen_h * 0.35,
             50.0,
             TEXT_ACCENT,
         );
 
-        // High score
-        if self.high_score > 0.0 {
-            let high_score_text = format!("High Score: {:.0}", self.high_score);
-            let score_dims = measure_text(&high_score_text, None, 25, 1.0);
+        // The replay to race against, if any
+        let ghost_text = match &self.ghost_replay {
+            Some((path, replay)) => format!("Ghost: {} ({:.0})", path, replay.score),
+            None => "Ghost: off".to_string(),
+        };
+        draw_text(&ghost_text, 10.0, 30.0, 20.0, TEXT_SECONDARY);
+
+        // Local co-op, with the second player on WASD
+        let coop_text = match (self.coop, self.mode.allows_coop()) {
+            (false, _) => "Co-op: off".to_string(),
+            (true, false) => "Co-op: not in this mode".to_string(),
+            (true, true) => {
+                let lives = if self.shared_lives { "shared" } else { "separate" };
+                format!("Co-op: P2 on A/D, W, LSHIFT ({} lives)", lives)
+            }
+        };
+        draw_text(&coop_text, 10.0, 50.0, 20.0, TEXT_SECONDARY);
+
+        // Mode selection
+        let mode_text = format!("Mode: {}", self.mode.name());
+        let mode_dims = measure_text(&mode_text, None, 25, 1.0);
+        draw_text(
+            &mode_text,
+            screen_w * 0.5 - mode_dims.width * 0.5,
+            screen_h * 0.44,
+            25.0,
+            TEXT_ACCENT,
+        );
+
+        // The daily has its own bests and a fixed difficulty
+        if self.mode == GameMode::Daily {
+            self.draw_daily_menu();
+        } else {
+            // High score for the selected mode and difficulty, or the time to beat
+            let high_score = self.high_scores.best(self.mode, self.preset, self.adaptive.enabled);
+            let best_time = self.best_splits.best(&self.level.id, self.preset).and_then(|splits| splits.last().copied());
+            if let (GameMode::TimeAttack, Some(best_time)) = (self.mode, best_time) {
+                let best_text = format!("Personal Best: {}", format_ticks(best_time));
+                let best_dims = measure_text(&best_text, None, 25, 1.0);
+                draw_text(
+                    &best_text,
+                    screen_w * 0.5 - best_dims.width * 0.5,
+                    screen_h * 0.5,
+                    25.0,
+                    TEXT_PRIMARY,
+                );
+            } else if self.mode == GameMode::Versus {
+                let versus_text = "Player two steers the shadow: A/D, W, LSHIFT";
+                let versus_dims = measure_text(versus_text, None, 25, 1.0);
+                draw_text(
+                    versus_text,
+                    screen_w * 0.5 - versus_dims.width * 0.5,
+                    screen_h * 0.5,
+                    25.0,
+                    TEXT_PRIMARY,
+                );
+            } else if high_score > 0.0 && self.mode != GameMode::TimeAttack {
+                let high_score_text = format!("High Score: {:.0}", high_score);
+                let score_dims = measure_text(&high_score_text, None, 25, 1.0);
+                draw_text(
+                    &high_score_text,
+                    screen_w * 0.5 - score_dims.width * 0.5,
+                    screen_h * 0.5,
+                    25.0,
+                    TEXT_PRIMARY,
+                );
+            }
+
+            // Preset selection
+            let preset_text = if self.adaptive.enabled {
+                format!("< {} + Adaptive >", self.preset.name())
+            } else {
+                format!("< {} >", self.preset.name())
+            };
+            let preset_dims = measure_text(&preset_text, None, 25, 1.0);
             draw_text(
-                &high_score_text,
-                screen_w * 0.5 - score_dims.width * 0.5,
-                screen_h * 0.5,
+                &preset_text,
+                screen_w * 0.5 - preset_dims.width * 0.5,
+                screen_h * 0.55,
                 25.0,
-                TEXT_PRIMARY,
+                TEXT_ACCENT,
+            );
+        }
+
+        if self.settings.reduce_motion {
+            let motion_text = "Reduce motion: ON";
+            let motion_dims = measure_text(motion_text, None, 20, 1.0);
+            draw_text(
+                motion_text,
+                screen_w * 0.5 - motion_dims.width * 0.5,
+                screen_h * 0.64,
+                20.0,
+                TEXT_SECONDARY,
             );
         }
 
@@ -407,11 +1550,15 @@ impl GameState {
         );
 
         // Controls
-        let controls_text = vec![
+        let controls_text = [
             "Controls:",
             "LEFT/RIGHT - Move",
-            "SPACE - Jump",
-            "ESC - Pause",
+            "SPACE - Jump (again in the air, or off walls)",
+            "SHIFT - Dash, R - Rewind (hold), E - Swap with shadow",
+            "ESC - Pause, C - Co-op, L - Shared lives",
+            "Menu: UP/DOWN - Mode, LEFT/RIGHT - Difficulty, A - Adaptive, G - Ghost",
+            "M - Reduce motion, F2 - Pixel scaling",
+            "ALT+ENTER - Toggle fullscreen",
         ];
 
         for (i, text) in controls_text.iter().enumerate() {
@@ -419,23 +1566,64 @@ impl GameState {
             draw_text(
                 text,
                 screen_w * 0.5 - dims.width * 0.5,
-                screen_h * 0.7 + i as f32 * 25.0,
+                screen_h * 0.68 + i as f32 * 22.0,
                 20.0,
                 TEXT_SECONDARY,
             );
         }
     }
 
+    fn draw_daily_menu(&self) {
+        let screen_w = WINDOW_WIDTH;
+        let screen_h = WINDOW_HEIGHT;
+        let today = Date::today();
+
+        let best_text = match self.daily_log.best(today) {
+            Some(best) => format!("Today's best: {:.0} ({} tries)", best, self.daily_log.attempts(today)),
+            None => "Not played yet today".to_string(),
+        };
+        let best_dims = measure_text(&best_text, None, 25, 1.0);
+        draw_text(
+            &best_text,
+            screen_w * 0.5 - best_dims.width * 0.5,
+            screen_h * 0.5,
+            25.0,
+            TEXT_PRIMARY,
+        );
+
+        let mutators: Vec<&str> = Mutator::for_seed(today.seed()).iter().map(|mutator| mutator.name()).collect();
+        let daily_text = format!("{}: {}", today.name(), mutators.join(" + "));
+        let daily_dims = measure_text(&daily_text, None, 25, 1.0);
+        draw_text(
+            &daily_text,
+            screen_w * 0.5 - daily_dims.width * 0.5,
+            screen_h * 0.55,
+            25.0,
+            TEXT_ACCENT,
+        );
+
+        // Past attempts, in the corner
+        self.daily_log.draw_calendar(today, screen_w - 190.0, 30.0);
+        let best_ever = self.daily_log.best_ever();
+        if best_ever > 0.0 {
+            draw_text(&format!("Best daily: {:.0}", best_ever), screen_w - 190.0, 210.0, 20.0, TEXT_SECONDARY);
+        }
+    }
+
     fn draw_game_over(&mut self) {
         self.draw_playing();
 
-        let screen_w = screen_width();
-        let screen_h = screen_height();
+        let screen_w = WINDOW_WIDTH;
+        let screen_h = WINDOW_HEIGHT;
 
         draw_rectangle(0.0, 0.0, screen_w, screen_h, Color::new(0.0, 0.0, 0.0, 0.9));
 
-        // Game Over text in warning color
-        let game_over_text = "GAME OVER";
+        // Game Over text in warning color, versus has a winner instead
+        let game_over_text = match self.mode {
+            GameMode::Versus if self.lives > 0 => "RUNNER WINS",
+            GameMode::Versus => "SHADOW WINS",
+            _ => "GAME OVER",
+        };
         let text_dims = measure_text(game_over_text, None, 50, 1.0);
         draw_text(
             game_over_text,
@@ -446,7 +1634,11 @@ impl GameState {
         );
 
         // Score in accent color
-        let score_text = format!("Final Score: {:.0}", self.score);
+        let score_text = match &self.partner {
+            Some(partner) => format!("P1: {:.0}   P2: {:.0}", self.score, partner.score),
+            None if self.mode == GameMode::Versus => format!("Survived: {:.0}s / {:.0}s", self.score, self.config.versus_time),
+            None => format!("Final Score: {:.0}", self.score),
+        };
         let score_dims = measure_text(&score_text, None, 30, 1.0);
         draw_text(
             &score_text,
@@ -457,8 +1649,8 @@ impl GameState {
         );
 
         // High Score in gold
-        if self.score > self.high_score {
-            let new_high_score_text = "New High Score!";
+        if self.new_high_score {
+            let new_high_score_text = if self.mode == GameMode::Daily { "New Daily Best!" } else { "New High Score!" };
             let high_score_dims = measure_text(new_high_score_text, None, 25, 1.0);
             draw_text(
                 new_high_score_text,
@@ -470,7 +1662,7 @@ impl GameState {
         }
 
         // Instructions in secondary color
-        let instructions = vec![
+        let instructions = [
             "Press SPACE to play again",
             "Press ESC for main menu",
         ];
@@ -485,47 +1677,262 @@ impl GameState {
                 TEXT_SECONDARY,
             );
         }
+
+        // Generated runs can be replayed from their seed
+        if matches!(self.mode, GameMode::Generated | GameMode::Runner) {
+            let seed_text = format!("Seed: {} (run with --seed {} to replay it)", self.seed, self.seed);
+            let seed_dims = measure_text(&seed_text, None, 20, 1.0);
+            draw_text(
+                &seed_text,
+                screen_w * 0.5 - seed_dims.width * 0.5,
+                screen_h * 0.8,
+                20.0,
+                TEXT_SECONDARY,
+            );
+        }
+
+        // The best daily run of the day is kept, to share or watch again
+        if let Some(path) = &self.saved_replay {
+            let replay_text = format!("Replay saved to {} (watch it with --replay)", path);
+            let replay_dims = measure_text(&replay_text, None, 20, 1.0);
+            draw_text(
+                &replay_text,
+                screen_w * 0.5 - replay_dims.width * 0.5,
+                screen_h * 0.8,
+                20.0,
+                TEXT_SECONDARY,
+            );
+        }
+    }
+
+    fn draw_results(&mut self) {
+        self.draw_playing();
+
+        let screen_w = WINDOW_WIDTH;
+        let screen_h = WINDOW_HEIGHT;
+        let time_attack = &self.time_attack;
+
+        draw_rectangle(0.0, 0.0, screen_w, screen_h, Color::new(0.0, 0.0, 0.0, 0.9));
+
+        let title_text = "FINISHED";
+        let title_dims = measure_text(title_text, None, 50, 1.0);
+        draw_text(title_text, screen_w * 0.5 - title_dims.width * 0.5, screen_h * 0.25, 50.0, TEXT_ACCENT);
+
+        let final_ticks = time_attack.final_ticks().unwrap_or(time_attack.ticks);
+        let time_text = format!("Time: {}", format_ticks(final_ticks));
+        let time_dims = measure_text(&time_text, None, 30, 1.0);
+        draw_text(&time_text, screen_w * 0.5 - time_dims.width * 0.5, screen_h * 0.34, 30.0, TEXT_PRIMARY);
+
+        // Compared with the personal best from before this run
+        let best_text = match time_attack.best.as_ref().and_then(|best| best.last()) {
+            _ if self.new_high_score => "New Personal Best!".to_string(),
+            Some(best) => format!("Personal best: {}", format_ticks(*best)),
+            None => String::new(),
+        };
+        let best_dims = measure_text(&best_text, None, 25, 1.0);
+        let best_color = if self.new_high_score { TEXT_GOLD } else { TEXT_SECONDARY };
+        draw_text(&best_text, screen_w * 0.5 - best_dims.width * 0.5, screen_h * 0.34 + 35.0, 25.0, best_color);
+
+        // Split by split
+        for i in 0..time_attack.split_count() {
+            let y = screen_h * 0.5 + i as f32 * 25.0;
+            draw_text(&time_attack.split_name(i), screen_w * 0.5 - 160.0, y, 20.0, TEXT_SECONDARY);
+            if let Some(ticks) = time_attack.splits.get(i) {
+                draw_text(&format_ticks(*ticks), screen_w * 0.5 - 20.0, y, 20.0, TEXT_PRIMARY);
+            }
+            if let Some(delta) = time_attack.delta(i) {
+                let color = if delta < 0 { TEXT_GOLD } else { TEXT_WARNING };
+                draw_text(&format_delta(delta), screen_w * 0.5 + 90.0, y, 20.0, color);
+            }
+        }
+
+        let instructions = ["Press SPACE to try again", "Press ESC for main menu"];
+        for (i, text) in instructions.iter().enumerate() {
+            let dims = measure_text(text, None, 20, 1.0);
+            draw_text(text, screen_w * 0.5 - dims.width * 0.5, screen_h * 0.85 + i as f32 * 30.0, 20.0, TEXT_SECONDARY);
+        }
+    }
+
+    fn draw_lives(&self, lives: i32, start_y: f32, full: Color) {
+        let heart_size = 20.0;
+        let spacing = 5.0;
+        let start_x = WINDOW_WIDTH - (heart_size + spacing) * self.config.lives as f32;
+
+        for i in 0..self.config.lives {
+            let x = start_x + (heart_size + spacing) * i as f32;
+            let color = if i < lives { full } else { GRAY };
+
+            // Draw a simple heart shape
+            draw_poly(x + heart_size / 2.0, start_y + heart_size / 2.0, 3, heart_size / 2.0, 0.0, color);
+            draw_circle(x + heart_size / 3.0, start_y + heart_size / 3.0, heart_size / 4.0, color);
+            draw_circle(x + 2.0 * heart_size / 3.0, start_y + heart_size / 3.0, heart_size / 4.0, color);
+        }
+    }
+
+    fn draw_ui(&self, light: Color) {
+        let hud = |color| tinted(color, light);
+        // Draw basic info
+        draw_text("Cha(se)dow", 10.0, 30.0, 50.0, hud(TEXT_ACCENT));
+        if self.mode == GameMode::TimeAttack {
+            self.draw_time_attack_hud(light);
+        } else if self.mode == GameMode::Versus {
+            let time_left = (self.config.versus_time - self.run_time).max(0.0);
+            draw_text(&format!("Survive: {:.0}s", time_left.ceil()), 10.0, 60.0, 20.0, hud(TEXT_ACCENT));
+        } else if let Some(partner) = &self.partner {
+            draw_text(&format!("P1 Score: {:.0} / Coins: {}", self.score, self.coin_points), 10.0, 60.0, 20.0, hud(TEXT_ACCENT));
+            draw_text(
+                &format!("P2 Score: {:.0} / Coins: {}", partner.score, partner.coin_points),
+                10.0, 80.0, 20.0,
+                PLAYER_TWO_TINT,
+            );
+        } else {
+            let high_score = match self.mode {
+                GameMode::Daily => self.daily_log.best(self.daily_date).unwrap_or(0.0),
+                _ => self.high_scores.best(self.mode, self.preset, self.adaptive.enabled),
+            };
+            draw_text(
+                &format!("{}: {:.0} / High Score: {:.0} ", self.mode.score_label(), self.score, high_score),
+                10.0, 60.0, 20.0,
+                hud(TEXT_ACCENT),
+            );
+        }
+
+        // Draw lives, a second row for the second player unless they share them
+        self.draw_lives(self.lives, 10.0, RED);
+        let mut timer_y = 45.0;
+        if let Some(partner) = self.partner.as_ref().filter(|_| !self.shared_lives) {
+            self.draw_lives(partner.lives, 35.0, PLAYER_TWO_TINT);
+            timer_y += 45.0;  // Below the danger meter
+        }
+
+        // Draw invulnerability timer if active
+        if self.is_invulnerable {
+            draw_text(
+                &format!("(invulnerability: {:.0}s)", self.invulnerable_timer),
+                WINDOW_WIDTH - 190.0, timer_y, 20.0, hud(TEXT_SECONDARY),
+            );
+        }
+
+        // Add coin points to UI, co-op shows them with each player's score
+        if self.partner.is_none() {
+            draw_text(
+                &format!("Coins: {}", self.coin_points),
+                10.0, 80.0, 20.0,
+                hud(TEXT_ACCENT)
+            );
+        }
+
+        // Combo multiplier with a bar showing the time left before it decays
+        if self.combo_multiplier > 1 {
+            draw_text(
+                &format!("Combo x{}", self.combo_multiplier),
+                10.0, 100.0, 20.0,
+                hud(TEXT_GOLD)
+            );
+            let bar_width = 80.0 * (self.combo_timer / COMBO_WINDOW);
+            draw_rectangle(10.0, 106.0, bar_width, 4.0, hud(TEXT_GOLD));
+        }
+
+        self.draw_danger_meter(light);
+
+        // Rewind and swap are for solo runs only
+        if self.partner.is_some() || self.hunter.is_some() {
+            return;
+        }
+
+        // Rewind charge, filled by coins
+        if self.mode.allows_rewind() {
+            let rewind_color = hud(if self.rewinding { TEXT_ACCENT } else { TEXT_SECONDARY });
+            draw_text("Rewind (R)", 10.0, 130.0, 20.0, rewind_color);
+            draw_rectangle_lines(100.0, 120.0, 80.0, 10.0, 2.0, hud(TEXT_SECONDARY));
+            draw_rectangle(100.0, 120.0, 80.0 * self.rewind.charge_fraction(), 10.0, rewind_color);
+        }
+
+        // Swap cooldown, full when it's ready
+        let swap_ready = self.swap_cooldown <= 0.0;
+        let swap_color = hud(if swap_ready { TEXT_ACCENT } else { TEXT_SECONDARY });
+        draw_text("Swap (E)", 10.0, 150.0, 20.0, swap_color);
+        draw_rectangle_lines(100.0, 140.0, 80.0, 10.0, 2.0, hud(TEXT_SECONDARY));
+        draw_rectangle(100.0, 140.0, 80.0 * (1.0 - self.swap_cooldown / SWAP_COOLDOWN), 10.0, swap_color);
     }
 
-    fn draw_lives(&self) {
-        let heart_size = 20.0;
-        let spacing = 5.0;
-        let start_x = 725.0;
-        let start_y = 10.0;
+    // The clock, the delta to the personal best and the splits so far
+    fn draw_time_attack_hud(&self, light: Color) {
+        let hud = |color| tinted(color, light);
+        let time_attack = &self.time_attack;
+        draw_text(&time_attack.goal_text(), 10.0, 60.0, 20.0, hud(TEXT_ACCENT));
+
+        let clock = format_ticks(time_attack.ticks);
+        let clock_dims = measure_text(&clock, None, 40, 1.0);
+        draw_text(&clock, WINDOW_WIDTH * 0.5 - clock_dims.width * 0.5, 35.0, 40.0, hud(TEXT_PRIMARY));
+
+        let delta = time_attack.live_delta().or_else(|| time_attack.delta(time_attack.splits.len().checked_sub(1)?));
+        if let Some(delta) = delta {
+            let delta_text = format_delta(delta);
+            let delta_dims = measure_text(&delta_text, None, 25, 1.0);
+            let color = hud(if delta < 0 { TEXT_GOLD } else { TEXT_WARNING });
+            draw_text(&delta_text, WINDOW_WIDTH * 0.5 - delta_dims.width * 0.5, 60.0, 25.0, color);
+        }
 
-        for i in 0..INITIAL_LIVES {
-            let x = start_x + (heart_size + spacing) * i as f32;
-            let color = if i < self.lives { RED } else { GRAY };
+        // Done splits show their time and delta, the rest the personal best to beat
+        for i in 0..time_attack.split_count() {
+            let y = 95.0 + i as f32 * 20.0;
+            let done = time_attack.splits.get(i);
+            let color = hud(if done.is_some() { TEXT_ACCENT } else { TEXT_SECONDARY });
+            draw_text(&time_attack.split_name(i), WINDOW_WIDTH - 230.0, y, 20.0, color);
 
-            // Draw a simple heart shape
-            draw_poly(x + heart_size / 2.0, start_y + heart_size / 2.0, 3, heart_size / 2.0, 0.0, color);
-            draw_circle(x + heart_size / 3.0, start_y + heart_size / 3.0, heart_size / 4.0, color);
-            draw_circle(x + 2.0 * heart_size / 3.0, start_y + heart_size / 3.0, heart_size / 4.0, color);
+            let ticks = done.or_else(|| time_attack.best.as_ref()?.get(i));
+            if let Some(ticks) = ticks {
+                draw_text(&format_ticks(*ticks), WINDOW_WIDTH - 140.0, y, 20.0, color);
+            }
+            if let Some(delta) = time_attack.delta(i) {
+                let color = hud(if delta < 0 { TEXT_GOLD } else { TEXT_WARNING });
+                draw_text(&format_delta(delta), WINDOW_WIDTH - 65.0, y, 20.0, color);
+            }
         }
     }
 
-    fn draw_ui(&self) {
-        // Draw basic info
-        draw_text("Cha(se)dow", 10.0, 30.0, 50.0, TEXT_ACCENT);
-        draw_text(&format!("Score: {:.0} / High Score: {:.0} ", self.score, self.high_score), 10.0, 60.0, 20.0, TEXT_ACCENT);
+    fn draw_danger_meter(&self, light: Color) {
+        let hud = |color| tinted(color, light);
+        let bar_w = 110.0;
+        let bar_x = WINDOW_WIDTH - bar_w - 10.0;
+        let bar_y = 58.0;
+        let bar_h = 10.0;
 
-        // Draw lives
-        self.draw_lives();
+        // 0.0 when the shadow is far away, 1.0 when it is touching the player
+        let danger = (1.0 - self.shadow_gap / DANGER_RANGE).clamp(0.0, 1.0);
+        let color = hud(if self.shadow_gap < NEAR_MISS_DISTANCE { TEXT_WARNING } else { TEXT_SECONDARY });
 
-        // Draw invulnerability timer if active
-        if self.is_invulnerable {
-            draw_text(
-                &format!("(invulnerability: {:.0}s)", self.invulnerable_timer),
-                610.0, 45.0, 20.0, TEXT_SECONDARY,
-            );
-        }
+        draw_text("Danger", bar_x - 70.0, 68.0, 20.0, hud(TEXT_SECONDARY));
+        draw_rectangle_lines(bar_x, bar_y, bar_w, bar_h, 2.0, hud(TEXT_SECONDARY));
+        draw_rectangle(bar_x, bar_y, bar_w * danger, bar_h, color);
+    }
+}
 
-        // Add coin points to UI
-        draw_text(
-            &format!("Coins: {}", self.coin_points),
-            10.0, 80.0, 20.0,
-            TEXT_ACCENT
-        );
+// What the player is doing on a given frame, recorded in the shadow's trail
+#[derive(Clone, Copy, PartialEq)]
+enum MoveState {
+    Grounded,
+    Airborne,
+    WallSlide,
+    Dashing,
+}
+
+fn mix_color(from: Color, to: Color, t: f32) -> Color {
+    Color::new(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+        from.a + (to.a - from.a) * t,
+    )
+}
+
+// Move `current` towards `target` by at most `max_delta`
+fn approach(current: f32, target: f32, max_delta: f32) -> f32 {
+    if current < target {
+        (current + max_delta).min(target)
+    } else {
+        (current - max_delta).max(target)
     }
 }
 
@@ -535,10 +1942,42 @@ struct Player {
     size: Vec2,
     texture: Texture2D,
     sprite: AnimatedSprite,
+    physics: PhysicsConfig,
+    abilities: Abilities,
+    state: MoveState,
+    facing: f32,
+    coyote_timer: f32,
+    jump_buffer_timer: f32,
+    is_jumping: bool,
+    air_jumps_left: u32,
+    wall_jump_lock_timer: f32,
+    dash_timer: f32,
+    dash_cooldown_timer: f32,
+    was_on_ground: bool,
+    just_jumped: bool,
+    just_landed: bool,
+    screen_edges: bool,  // The sides of the screen count as walls, except in the runner
+    tint: Color,
+}
+
+// The part of the player that moves on from frame to frame, for the rewind's snapshots
+#[derive(Clone, Copy)]
+struct PlayerMotion {
+    speed: Vec2,
+    state: MoveState,
+    facing: f32,
+    coyote_timer: f32,
+    jump_buffer_timer: f32,
+    is_jumping: bool,
+    air_jumps_left: u32,
+    wall_jump_lock_timer: f32,
+    dash_timer: f32,
+    dash_cooldown_timer: f32,
+    was_on_ground: bool,
 }
 
 impl Player {
-    async fn new(world: &mut World) -> Self {
+    async fn new(world: &mut World, physics: PhysicsConfig) -> Self {
         set_pc_assets_folder("assets");
         let texture = load_texture("player.png").await.expect("Couldn't load player texture");
         texture.set_filter(FilterMode::Nearest);
@@ -569,49 +2008,217 @@ impl Player {
             size: PLAYER_SIZE,
             texture,
             sprite,
+            physics,
+            abilities: Abilities::default(),
+            state: MoveState::Grounded,
+            facing: 1.0,
+            coyote_timer: 0.0,
+            jump_buffer_timer: 0.0,
+            is_jumping: false,
+            air_jumps_left: 0,
+            wall_jump_lock_timer: 0.0,
+            dash_timer: 0.0,
+            dash_cooldown_timer: 0.0,
+            was_on_ground: true,
+            just_jumped: false,
+            just_landed: false,
+            screen_edges: true,
+            tint: WHITE,
+        }
+    }
+
+    fn motion(&self) -> PlayerMotion {
+        PlayerMotion {
+            speed: self.speed,
+            state: self.state,
+            facing: self.facing,
+            coyote_timer: self.coyote_timer,
+            jump_buffer_timer: self.jump_buffer_timer,
+            is_jumping: self.is_jumping,
+            air_jumps_left: self.air_jumps_left,
+            wall_jump_lock_timer: self.wall_jump_lock_timer,
+            dash_timer: self.dash_timer,
+            dash_cooldown_timer: self.dash_cooldown_timer,
+            was_on_ground: self.was_on_ground,
         }
     }
 
-    fn update(&mut self, world: &mut World) {
+    fn set_motion(&mut self, motion: PlayerMotion) {
+        self.speed = motion.speed;
+        self.state = motion.state;
+        self.facing = motion.facing;
+        self.coyote_timer = motion.coyote_timer;
+        self.jump_buffer_timer = motion.jump_buffer_timer;
+        self.is_jumping = motion.is_jumping;
+        self.air_jumps_left = motion.air_jumps_left;
+        self.wall_jump_lock_timer = motion.wall_jump_lock_timer;
+        self.dash_timer = motion.dash_timer;
+        self.dash_cooldown_timer = motion.dash_cooldown_timer;
+        self.was_on_ground = motion.was_on_ground;
+    }
+
+    fn update(&mut self, world: &mut World, controls: Controls, dt: f32) {
         let pos = world.actor_pos(self.collider);
         let on_ground = world.collide_check(self.collider, pos + vec2(0., 1.));
+        let wall_dir = self.wall_direction(world, pos);
 
-        self.handle_movement(on_ground);
-        self.apply_movement(world);
+        self.just_landed = on_ground && !self.was_on_ground;
+        self.was_on_ground = on_ground;
+        self.just_jumped = false;
+
+        self.handle_movement(on_ground, wall_dir, controls, dt);
+        self.apply_movement(world, dt);
+    }
+
+    // -1.0 when touching a wall on the left, 1.0 on the right, 0.0 otherwise
+    fn wall_direction(&self, world: &World, pos: Vec2) -> f32 {
+        if (self.screen_edges && pos.x <= 0.0) || world.collide_check(self.collider, pos - vec2(1., 0.)) {
+            -1.0
+        } else if (self.screen_edges && pos.x >= WINDOW_WIDTH - self.size.x) || world.collide_check(self.collider, pos + vec2(1., 0.)) {
+            1.0
+        } else {
+            0.0
+        }
     }
 
-    fn handle_movement(&mut self, on_ground: bool) {
+    fn handle_movement(&mut self, on_ground: bool, wall_dir: f32, controls: Controls, dt: f32) {
+        let physics = self.physics;
+        let abilities = self.abilities;
+
+        // Coyote time: the player can still jump shortly after leaving a ledge
+        if on_ground {
+            self.coyote_timer = physics.coyote_time;
+        } else {
+            self.coyote_timer -= dt;
+        }
+
+        // Jump buffering: a press just before landing still counts
+        if controls.jump_pressed {
+            self.jump_buffer_timer = physics.jump_buffer_time;
+        } else {
+            self.jump_buffer_timer -= dt;
+        }
+
+        self.wall_jump_lock_timer -= dt;
+        self.dash_cooldown_timer -= dt;
+        if on_ground {
+            self.air_jumps_left = if abilities.double_jump { 1 } else { 0 };
+        }
+
+        let direction = match (controls.right, controls.left) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+        if direction != 0.0 && self.wall_jump_lock_timer <= 0.0 {
+            self.facing = direction;
+        }
+
+        // Dashing overrides everything else, gravity included
+        if abilities.dash && controls.dash_pressed && self.dash_cooldown_timer <= 0.0 {
+            self.dash_timer = physics.dash_duration;
+            self.dash_cooldown_timer = physics.dash_cooldown;
+        }
+        if self.dash_timer > 0.0 {
+            self.dash_timer -= dt;
+            self.speed = vec2(self.facing * physics.dash_speed, 0.0);
+            if self.dash_timer <= 0.0 {
+                self.speed.x = self.facing * physics.max_speed;
+            }
+            self.is_jumping = false;
+            self.state = MoveState::Dashing;
+            self.sprite.set_animation(1);
+            return;
+        }
+
+        let wall_sliding = abilities.wall_slide
+            && !on_ground
+            && wall_dir != 0.0
+            && direction == wall_dir
+            && self.speed.y > 0.0;
+
         // Apply gravity when in air
         if !on_ground {
             self.sprite.set_animation(1);
-            self.speed.y += GRAVITY * get_frame_time();
+            let gravity = if self.speed.y < 0.0 { physics.rise_gravity } else { physics.fall_gravity };
+            self.speed.y += gravity * dt;
+            if wall_sliding {
+                self.speed.y = self.speed.y.min(physics.wall_slide_speed);
+            }
         } else {
             self.sprite.set_animation(0);
+            // Don't carry the landing speed over into the next fall
+            if self.speed.y > 0.0 {
+                self.speed.y = 0.0;
+            }
         }
 
         // Handle horizontal movement
-        self.speed.x = match (is_key_down(KeyCode::Right), is_key_down(KeyCode::Left)) {
-            (true, false) => PLAYER_SPEED,
-            (false, true) => -PLAYER_SPEED,
-            _ => 0.0,
-        };
+        if self.wall_jump_lock_timer <= 0.0 {
+            let rate = match (on_ground, direction != 0.0) {
+                (true, true) => physics.ground_acceleration,
+                (true, false) => physics.ground_deceleration,
+                (false, true) => physics.air_acceleration,
+                (false, false) => physics.air_deceleration,
+            };
+            self.speed.x = approach(self.speed.x, direction * physics.max_speed, rate * dt);
+        }
+
+        // Handle jumping: ground (with coyote time) first, then walls, then the air jump
+        if self.jump_buffer_timer > 0.0 {
+            let can_wall_jump = abilities.wall_slide && !on_ground && wall_dir != 0.0;
+            let mut jumped = true;
+
+            if self.coyote_timer > 0.0 {
+                self.speed.y = physics.jump_force;
+            } else if can_wall_jump {
+                self.speed = vec2(-wall_dir * physics.wall_jump_push, physics.jump_force);
+                self.facing = -wall_dir;
+                self.wall_jump_lock_timer = physics.wall_jump_lock;
+                self.air_jumps_left = if abilities.double_jump { 1 } else { 0 };
+            } else if self.air_jumps_left > 0 {
+                self.speed.y = physics.double_jump_force;
+                self.air_jumps_left -= 1;
+            } else {
+                jumped = false;
+            }
+
+            if jumped {
+                self.just_jumped = true;
+                self.jump_buffer_timer = 0.0;
+                self.coyote_timer = 0.0;
+                self.is_jumping = true;
+            }
+        }
 
-        // Handle jumping
-        if is_key_pressed(KeyCode::Space) && on_ground {
-            self.speed.y = JUMP_FORCE;
+        // Releasing Space early cuts the jump short
+        if self.is_jumping && self.speed.y < 0.0 && !controls.jump_held {
+            self.speed.y *= physics.jump_cut;
+            self.is_jumping = false;
         }
+        if self.speed.y >= 0.0 {
+            self.is_jumping = false;
+        }
+
+        self.state = if on_ground {
+            MoveState::Grounded
+        } else if wall_sliding {
+            MoveState::WallSlide
+        } else {
+            MoveState::Airborne
+        };
     }
 
-    fn apply_movement(&mut self, world: &mut World) {
-        world.move_h(self.collider, self.speed.x * get_frame_time());
-        world.move_v(self.collider, self.speed.y * get_frame_time());
+    fn apply_movement(&mut self, world: &mut World, dt: f32) {
+        world.move_h(self.collider, self.speed.x * dt);
+        world.move_v(self.collider, self.speed.y * dt);
     }
 
     fn draw(&mut self, world: &World) {
         let player_frame = self.sprite.frame();
 
         // Do not update to next frame if :
-        let is_last_jump_frame = player_frame.source_rect.x == 16.*2. && player_frame.source_rect.y == 16.*2. && self.speed.y != 0.0;
+        let is_last_jump_frame = player_frame.source_rect.x == 16.*2. && self.sprite.current_animation() == 1 && self.speed.y != 0.0;
 
         if !(is_last_jump_frame) {
             self.sprite.update();
@@ -622,26 +2229,72 @@ impl Player {
             &self.texture,
             pos.x,
             pos.y,
-            WHITE,
+            self.tint,
             DrawTextureParams {
                 dest_size: Some(vec2(self.size.x, self.size.y)),
                 source: Some(player_frame.source_rect),
-                flip_x: self.speed.x <= 0.,
+                flip_x: self.facing < 0.,
                 ..Default::default()
             },
         );
+    }
+}
+
+// The second player of a co-op run, chased by a shadow of their own
+struct Partner {
+    player: Player,
+    shadow: Shadow,
+    score: f32,
+    coin_points: i32,
+    lives: i32,                 // Only used when the players don't share their lives
+    invulnerable_timer: f32,
+    out: bool,                  // Ran out of lives while the first player plays on
+}
 
-        //fixme just for debug
-        // draw_rectangle_lines(pos.x, pos.y, self.size.x, self.size.y, 2., PLAYER_COLOR);
+impl Partner {
+    async fn new(world: &mut World, config: &GameConfig) -> Self {
+        let mut player = Player::new(world, config.physics).await;
+        player.tint = PLAYER_TWO_TINT;
+        Self {
+            player,
+            shadow: Shadow::new(config.shadow_delay).await,
+            score: 0.0,
+            coin_points: 0,
+            lives: config.lives,
+            invulnerable_timer: 0.0,
+            out: false,
+        }
     }
 }
 
+// One recorded frame of the player's movement
+#[derive(Clone, Copy)]
+struct TrailPoint {
+    pos: Vec2,
+    state: MoveState,
+}
+
+// Where the shadow is along the trail, for the rewind's snapshots
+struct ShadowTrail {
+    positions: Vec<TrailPoint>,
+    last_removed_position: Vec2,
+    passed: Vec<Vec2>,
+    delay_frames: usize,
+    visibility: f32,
+}
+
 struct Shadow {
-    positions: Vec<Vec2>,
+    positions: Vec<TrailPoint>,
     last_removed_position: Vec2,
+    passed: Vec<Vec2>,  // Where it has just been, newest first, for the afterimages
     delay_frames: usize,
     texture: Texture2D,
     sprite: AnimatedSprite,
+    wobble: bool,
+    opacity: f32,
+    afterimage_spacing: usize,
+    offset: Vec2,  // Where the sun casts it, relative to the trail
+    visibility: f32,  // Drops to 0.0 while the player hides in the shade
 }
 
 impl Shadow {
@@ -649,6 +2302,24 @@ impl Shadow {
         set_pc_assets_folder("assets");
         let texture = load_texture("player.png").await.expect("Couldn't load player texture");
         texture.set_filter(FilterMode::Nearest);
+
+        Self {
+            positions: vec![TrailPoint { pos: TRAIL_START, state: MoveState::Grounded }; delay_frames],
+            last_removed_position: vec2(50.0, 100.0),
+            passed: Vec::new(),
+            delay_frames,
+            texture,
+            sprite: Self::sprite(),
+            wobble: true,
+            opacity: 1.0,
+            afterimage_spacing: AFTERIMAGE_SPACING,
+            offset: Vec2::ZERO,
+            visibility: 1.0,
+        }
+    }
+
+    // The shadow rows of the player's sprite sheet
+    fn sprite() -> AnimatedSprite {
         let mut sprite = AnimatedSprite::new(
             12,
             12,
@@ -669,28 +2340,79 @@ impl Shadow {
             true,
         );
         sprite.set_animation(0);
+        sprite
+    }
 
-        Self {
-            positions: vec![vec2(50.0, 500.0); delay_frames],
-            last_removed_position: vec2(50.0, 100.0),
-            delay_frames,
-            texture,
-            sprite,
+    fn update(&mut self, player_pos: Vec2, state: MoveState, in_shade: bool, dt: f32) {
+        // In the shade nothing is recorded and the shadow stalls where it is,
+        // so once it starts moving again it skips straight past the shaded stretch
+        if in_shade {
+            self.visibility = (self.visibility - SHADE_FADE_SPEED * dt).max(0.0);
+            return;
+        }
+        self.visibility = (self.visibility + SHADE_FADE_SPEED * dt).min(1.0);
+
+        self.last_removed_position = self.positions.remove(0).pos;
+        self.passed.insert(0, self.last_removed_position);
+        self.passed.truncate(AFTERIMAGE_COUNT * (AFTERIMAGE_SPACING + AFTERIMAGE_EXTRA_SPACING as usize));
+        self.positions.push(TrailPoint { pos: player_pos, state });
+    }
+
+    fn trail(&self) -> ShadowTrail {
+        ShadowTrail {
+            positions: self.positions.clone(),
+            last_removed_position: self.last_removed_position,
+            passed: self.passed.clone(),
+            delay_frames: self.delay_frames,
+            visibility: self.visibility,
         }
     }
 
-    fn update(&mut self, player_pos: Vec2) {
-        self.last_removed_position = self.positions.remove(0);
-        self.positions.push(player_pos);
+    fn set_trail(&mut self, trail: ShadowTrail) {
+        self.positions = trail.positions;
+        self.last_removed_position = trail.last_removed_position;
+        self.passed = trail.passed;
+        self.delay_frames = trail.delay_frames;
+        self.visibility = trail.visibility;
+    }
 
+    // After swapping places: start from where the player was and walk the trail back to them
+    fn reverse_trail(&mut self) {
+        self.positions.reverse();
+        self.passed.clear();
+        if let Some(first) = self.positions.first() {
+            self.last_removed_position = first.pos;
+        }
     }
 
-    fn draw(&mut self) {
+    // Harmless while it's dissolving or coming back
+    fn is_solid(&self) -> bool {
+        self.visibility >= 1.0
+    }
+
+    // Grow by holding the shadow in place, shrink by skipping it ahead on the trail
+    fn set_delay(&mut self, delay_frames: usize) {
+        if delay_frames == self.delay_frames {
+            return;
+        }
+
+        let first = self.positions[0];
+        while self.positions.len() < delay_frames {
+            self.positions.insert(0, first);
+        }
+        if self.positions.len() > delay_frames {
+            let excess = self.positions.len() - delay_frames;
+            self.positions.drain(..excess);
+        }
+        self.delay_frames = delay_frames;
+    }
 
-        // fixme jump animation issue
-        if let Some(pos) = self.positions.first() {
+    fn draw(&mut self, player_pos: Vec2) {
 
-            let is_on_ground = self.last_removed_position.y == pos.y;
+        if let Some(&TrailPoint { pos, state }) = self.positions.first() {
+
+            // Replay whatever the player was doing at this point of the trail
+            let is_on_ground = state == MoveState::Grounded;
             if is_on_ground {
                 self.sprite.set_animation(0);
             }else{
@@ -706,36 +2428,183 @@ impl Shadow {
                 self.sprite.update();
             }
 
-            draw_texture_ex(
-                &self.texture,
-                pos.x,
-                pos.y,
-                WHITE,
-                DrawTextureParams {
-                    dest_size: Some(vec2(PLAYER_SIZE.x, PLAYER_SIZE.y)),
-                    source: Some(shadow_frame.source_rect),
-                    flip_x: pos.x < self.last_removed_position.x,
-                    ..Default::default()
-                },
-            );
+            // The closer the shadow gets, the more solid and vivid it looks
+            let gap = self.gap_to_player(player_pos).unwrap_or(DANGER_RANGE);
+            let closeness = (1.0 - gap / DANGER_RANGE).clamp(0.0, 1.0);
+            let mut tint = mix_color(SHADOW_COLOR, WHITE, closeness);
+            tint.a *= self.opacity * self.visibility;
+            let flip_x = pos.x < self.last_removed_position.x;
+
+            // Fading afterimages along the stretch of trail it has just left behind, furthest first
+            for i in (1..=AFTERIMAGE_COUNT).rev() {
+                if let Some(passed) = self.passed.get(i * self.afterimage_spacing - 1) {
+                    let fade = 1.0 - i as f32 / (AFTERIMAGE_COUNT + 1) as f32;
+                    let mut color = tint;
+                    color.a *= fade * AFTERIMAGE_ALPHA;
+                    let point_pos = *passed + self.offset + self.wobble_offset(i);
+                    self.draw_sprite(point_pos, shadow_frame.source_rect, color, flip_x);
+                }
+            }
+
+            self.draw_sprite(pos + self.offset + self.wobble_offset(0), shadow_frame.source_rect, tint, flip_x);
+        }
+    }
+
+    fn draw_sprite(&self, pos: Vec2, source: Rect, color: Color, flip_x: bool) {
+        draw_texture_ex(
+            &self.texture,
+            pos.x,
+            pos.y,
+            color,
+            DrawTextureParams {
+                dest_size: Some(vec2(PLAYER_SIZE.x, PLAYER_SIZE.y)),
+                source: Some(source),
+                flip_x,
+                ..Default::default()
+            },
+        );
+    }
 
-            //fixme just for debug
-            // draw_rectangle_lines(pos.x, pos.y, PLAYER_SIZE.x, PLAYER_SIZE.y, 3., PLAYER_COLOR);
+    // Purely visual: the hitbox always stays on the trail
+    fn wobble_offset(&self, index: usize) -> Vec2 {
+        if !self.wobble {
+            return Vec2::ZERO;
         }
+        let phase = get_time() as f32 * WOBBLE_SPEED + index as f32 * 0.8;
+        vec2(phase.sin() * WOBBLE_AMOUNT, 0.0)
+    }
+
+    // The sun's push moves the hitbox too, levels keep it small
+    fn set_offset(&mut self, offset: Vec2) {
+        self.offset = offset;
+    }
+
+    // Where the shadow actually is: its replay point, moved by the sun
+    fn position(&self) -> Option<Vec2> {
+        self.positions.first().map(|point| point.pos + self.offset)
+    }
+
+    fn hitbox(pos: Vec2) -> Rect {
+        //fixme fix sprite sheet file, remove margin
+        let no_margin_x = PLAYER_SIZE.x - 4. * 4.;
+        let no_margin_y = PLAYER_SIZE.y - 4. * 4.;
+        Rect::new(pos.x, pos.y, no_margin_x, no_margin_y)
     }
 
     fn collides_with_player(&self, player_pos: Vec2) -> bool {
-        if let Some(shadow_pos) = self.positions.first() {
-            //fixme fix sprite sheet file, remove margin
-            let no_margin_x = PLAYER_SIZE.x - 4. * 4.;
-            let no_margin_y = PLAYER_SIZE.y - 4. * 4.;
-            let shadow_rect = Rect::new(shadow_pos.x, shadow_pos.y, no_margin_x, no_margin_y);
-            let player_rect = Rect::new(player_pos.x, player_pos.y, no_margin_x, no_margin_y);
+        if !self.is_solid() {
+            return false;
+        }
+        if let Some(shadow_pos) = self.position() {
+            let shadow_rect = Self::hitbox(shadow_pos);
+            let player_rect = Self::hitbox(player_pos);
             shadow_rect.overlaps(&player_rect)
         } else {
             false
         }
     }
+
+    // Distance between the shadow and player hitboxes, 0.0 when they overlap
+    fn gap_to_player(&self, player_pos: Vec2) -> Option<f32> {
+        if !self.is_solid() {
+            return None;
+        }
+        Some(Self::gap(self.position()?, player_pos))
+    }
+
+    fn gap(shadow_pos: Vec2, player_pos: Vec2) -> f32 {
+        let shadow_rect = Self::hitbox(shadow_pos);
+        let player_rect = Self::hitbox(player_pos);
+
+        let dx = (shadow_rect.left() - player_rect.right())
+            .max(player_rect.left() - shadow_rect.right())
+            .max(0.0);
+        let dy = (shadow_rect.top() - player_rect.bottom())
+            .max(player_rect.top() - shadow_rect.bottom())
+            .max(0.0);
+        vec2(dx, dy).length()
+    }
+}
+
+// The shadow of a versus run: a body of its own, steered by the second player.
+// It's slower than the runner, and the runner's trail keeps it on a leash
+struct Hunter {
+    body: Player,
+    leash: f32,  // How far it can stray from where the trail would put the shadow
+}
+
+impl Hunter {
+    async fn new(world: &mut World, config: &GameConfig) -> Self {
+        let mut body = Player::new(world, Self::physics(config)).await;
+        world.set_actor_position(body.collider, TRAIL_START);
+        body.sprite = Shadow::sprite();
+        Self {
+            body,
+            leash: config.versus_leash,
+        }
+    }
+
+    fn physics(config: &GameConfig) -> PhysicsConfig {
+        let mut physics = config.physics;
+        physics.max_speed *= config.versus_speed;
+        physics.dash_speed *= config.versus_speed;
+        physics
+    }
+
+    fn apply_config(&mut self, config: &GameConfig) {
+        self.body.physics = Self::physics(config);
+        self.leash = config.versus_leash;
+    }
+
+    fn update(&mut self, world: &mut World, controls: Controls, anchor: Option<Vec2>, dt: f32) {
+        self.body.update(world, controls, dt);
+
+        // Past the end of the leash it gets dragged back, around platforms where it can be
+        let Some(anchor) = anchor else {
+            return;
+        };
+        let collider = self.body.collider;
+        let offset = world.actor_pos(collider) - anchor;
+        if offset.length() <= self.leash {
+            return;
+        }
+        let target = anchor + offset.normalize() * self.leash;
+        let pos = world.actor_pos(collider);
+        world.move_h(collider, target.x - pos.x);
+        let pos = world.actor_pos(collider);
+        world.move_v(collider, target.y - pos.y);
+
+        // Stuck behind a platform, it's snapped to the leash or the trail, whichever is free
+        if (world.actor_pos(collider) - anchor).length() > self.leash + 1.0 {
+            if let Some(spot) = [target, anchor].into_iter().find(|spot| !world.collide_check(collider, *spot)) {
+                world.set_actor_position(collider, spot);
+                self.body.speed = Vec2::ZERO;
+            }
+        }
+    }
+
+    fn tags(&self, world: &World, runner_pos: Vec2) -> bool {
+        self.gap_to(world, runner_pos) <= 0.0
+    }
+
+    fn gap_to(&self, world: &World, runner_pos: Vec2) -> f32 {
+        Shadow::gap(world.actor_pos(self.body.collider), runner_pos)
+    }
+
+    // The leash is drawn from the end of the trail, and the shadow fades with the light like the usual one
+    fn draw(&mut self, world: &World, anchor: Option<Vec2>, alpha: f32) {
+        let pos = world.actor_pos(self.body.collider);
+        let mut tint = SHADOW_COLOR;
+        tint.a *= alpha;
+        if let Some(anchor) = anchor {
+            let from = anchor + PLAYER_SIZE * 0.5;
+            let to = pos + PLAYER_SIZE * 0.5;
+            draw_line(from.x, from.y, to.x, to.y, 2.0, Color::new(tint.r, tint.g, tint.b, tint.a * 0.5));
+            draw_circle(from.x, from.y, 4.0, tint);
+        }
+        self.body.tint = tint;
+        self.body.draw(world);
+    }
 }
 
 struct Platform {
@@ -744,52 +2613,64 @@ struct Platform {
     size: Vec2,
     cactus_texture: Texture2D,
     // Store both position and size for each cactus
-    cacti: Vec<(f32, f32)>, // (position, size)
+    cacti: Vec<(f32, f32)>, // (offset from the platform's left edge, size)
+    range: (f32, f32),      // Left and right turning points of a moving platform
 }
 
 impl Platform {
-    async fn new(world: &mut World, pos: Vec2, size: Vec2, is_moving: bool) -> Self {
+    // A `speed` of 0.0 makes a static platform. Cacti are offsets from the left edge and sizes
+    async fn new(world: &mut World, pos: Vec2, size: Vec2, speed: f32, cacti: Vec<(f32, f32)>) -> Self {
         set_pc_assets_folder("assets");
         let cactus_texture: Texture2D = load_texture("player.png").await.unwrap();
         cactus_texture.set_filter(FilterMode::Nearest);
 
-        // Randomly decide to place 1 or 2 cacti
-        let num_cacti = gen_range(1, 3);
-
-        // Generate random positions and sizes along the platform
-        let mut cacti = Vec::new();
-        let min_size = 12.0 * 3.0; // Minimum size (36 pixels)
-        let max_size = 12.0 * 5.0; // Maximum size (60 pixels)
-
-        for _ in 0..num_cacti {
-            let cactus_size = gen_range(min_size, max_size);
-            let x_offset = gen_range(pos.x, pos.x + size.x - cactus_size);
-            cacti.push((x_offset, cactus_size));
-        }
-
         Self {
             collider: world.add_solid(pos, size.x as i32, size.y as i32),
-            speed: if is_moving { PLATFORM_SPEED } else { 0.0 },
+            speed,
             size,
             cactus_texture,
             cacti,
+            range: (150.0, 500.0),
         }
     }
 
-    fn update(&mut self, world: &mut World) {
+    // Change how fast a moving platform goes, keeping its current direction
+    fn set_speed(&mut self, speed: f32) {
         if self.speed != 0.0 {
-            world.solid_move(self.collider, self.speed * get_frame_time(), 0.0);
+            self.speed = speed * self.speed.signum();
+        }
+    }
+
+    // Teleport, e.g. to recycle a platform the climber view has left behind
+    fn move_to(&mut self, world: &mut World, pos: Vec2) {
+        let current = world.solid_pos(self.collider);
+        world.solid_move(self.collider, pos.x - current.x, pos.y - current.y);
+    }
+
+    fn update(&mut self, world: &mut World, dt: f32) {
+        if self.speed != 0.0 {
+            world.solid_move(self.collider, self.speed * dt, 0.0);
             let pos = world.solid_pos(self.collider);
 
-            if (self.speed > 1.0 && pos.x >= 500.0) || (self.speed < -1.0 && pos.x <= 150.0) {
+            if (self.speed > 1.0 && pos.x >= self.range.1) || (self.speed < -1.0 && pos.x <= self.range.0) {
                 self.speed *= -1.0;
             }
         }
     }
 
-    fn draw(&self, world: &World) {
+    // Only static platforms have cacti
+    fn cactus_hitboxes(&self, world: &World) -> Vec<Rect> {
+        if self.speed != 0.0 {
+            return Vec::new();
+        }
+        let pos = world.solid_pos(self.collider);
+        self.cacti.iter().map(|&(x_offset, size)| cactus_hitbox(pos, x_offset, size)).collect()
+    }
+
+    // `light` is the time of day's light, see `background::Palette`
+    fn draw(&self, world: &World, light: Color) {
         let pos = world.solid_pos(self.collider);
-        let color = if self.speed == 0.0 { STATIC_PLATFORM_COLOR } else { PLATFORM_COLOR };
+        let color = tinted(if self.speed == 0.0 { STATIC_PLATFORM_COLOR } else { PLATFORM_COLOR }, light);
 
         // Draw platform
         draw_rectangle(pos.x, pos.y, self.size.x, self.size.y, color);
@@ -800,9 +2681,9 @@ impl Platform {
                 let size = vec2(cactus_size, cactus_size);
                 draw_texture_ex(
                     &self.cactus_texture,
-                    x_offset,  // x position with offset
+                    pos.x + x_offset,  // x position with offset
                     pos.y - size.x,  // y position
-                    WHITE,
+                    light,
                     DrawTextureParams {
                         dest_size: Some(size),
                         source: Some(Rect::new(
@@ -819,49 +2700,154 @@ impl Platform {
     }
 }
 
-async fn create_platforms(world: &mut World) -> Vec<Platform> {
-    vec![
-        // Moving platform
-        Platform::new(world, vec2(100.0, 100.0), PLATFORM_SIZE, true).await,
+// The sprite has some empty space around the plant, only the middle of it hurts
+fn cactus_hitbox(platform_pos: Vec2, x_offset: f32, size: f32) -> Rect {
+    Rect::new(platform_pos.x + x_offset + size * 0.25, platform_pos.y - size * 0.75, size * 0.5, size * 0.75)
+}
+
+async fn create_platforms(world: &mut World, layout: &Layout, platform_speed: f32) -> Vec<Platform> {
+    let mut platforms = Vec::new();
+    for placed in &layout.platforms {
+        let mut platform = Platform::new(world, placed.pos, placed.size, placed.speed * platform_speed, placed.cacti.clone()).await;
+        platform.range = placed.range;
+        platforms.push(platform);
+    }
+    platforms
+}
+
+// Climber mode: the ground to start from, then a stack of platforms that gets recycled as the view climbs
+async fn create_climber_platforms(world: &mut World, platform_speed: f32) -> Vec<Platform> {
+    let mut platforms = vec![Platform::new(world, vec2(0.0, GROUND_Y), GROUND_SIZE, 0.0, climber_cacti(GROUND_SIZE.x)).await];
+
+    let mut y = GROUND_Y;
+    for i in 0..CLIMBER_PLATFORM_COUNT {
+        y -= gen_range(CLIMB_GAP_MIN, CLIMB_GAP_MAX);
+        let width = CLIMBER_PLATFORM_WIDTHS[i % CLIMBER_PLATFORM_WIDTHS.len()];
+        let speed = if i % CLIMBER_MOVING_EVERY == CLIMBER_MOVING_EVERY - 1 { platform_speed } else { 0.0 };
+        let x = climber_platform_x(width, speed);
+        platforms.push(Platform::new(world, vec2(x, y), vec2(width, PLATFORM_SIZE.y), speed, climber_cacti(width)).await);
+    }
+
+    platforms
+}
+
+// Layouts differ, so a spawn point is only a wish: the first free spot along its row, then the rows above
+fn spawn_offsets() -> impl Iterator<Item = Vec2> {
+    (0..6).flat_map(|row| {
+        (0..12).map(move |column| {
+            let side = if column % 2 == 0 { 1.0 } else { -1.0 };
+            vec2(side * ((column + 1) / 2) as f32 * PLAYER_SIZE.x, -(row as f32) * PLAYER_SIZE.y)
+        })
+    })
+}
+
+// One or two cacti somewhere along the platform
+fn climber_cacti(width: f32) -> Vec<(f32, f32)> {
+    (0..gen_range(1, 3))
+        .map(|_| {
+            let size = gen_range(CACTUS_SIZES.0, CACTUS_SIZES.1).min(width);
+            (gen_range(0.0, width - size), size)
+        })
+        .collect()
+}
+
+fn climber_platform_x(width: f32, speed: f32) -> f32 {
+    if speed != 0.0 {
+        // Moving platforms bounce between x 150 and 500
+        gen_range(150.0, 500.0)
+    } else {
+        gen_range(0.0, WINDOW_WIDTH - width)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CoinTier {
+    Common,
+    Rare,
+    Gold,
+}
 
-        // Static platforms
-        Platform::new(world, vec2(50.0, 200.0), PLATFORM_SIZE, false).await,
-        Platform::new(world, vec2(550.0, 200.0), PLATFORM_SIZE, false).await,
+impl CoinTier {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "common" => Some(CoinTier::Common),
+            "rare" => Some(CoinTier::Rare),
+            "gold" => Some(CoinTier::Gold),
+            _ => None,
+        }
+    }
+
+    fn random(config: &GameConfig, rng: &mut SeededRng) -> Self {
+        let weights = [
+            (CoinTier::Common, config.common_coin.weight),
+            (CoinTier::Rare, config.rare_coin.weight),
+            (CoinTier::Gold, config.gold_coin.weight),
+        ];
+        let total: u32 = weights.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return CoinTier::Common;
+        }
 
-        Platform::new(world, vec2(300.0, 300.0), PLATFORM_SIZE, false).await,
+        let mut roll = rng.gen_index(total as usize) as u32;
+        for (tier, weight) in weights {
+            if roll < weight {
+                return tier;
+            }
+            roll -= weight;
+        }
+        CoinTier::Common
+    }
 
-        Platform::new(world, vec2(50.0, 400.0), PLATFORM_SIZE, false).await,
-        Platform::new(world, vec2(550.0, 400.0), PLATFORM_SIZE, false).await,
+    fn stats(&self, config: &GameConfig) -> CoinTierConfig {
+        match self {
+            CoinTier::Common => config.common_coin,
+            CoinTier::Rare => config.rare_coin,
+            CoinTier::Gold => config.gold_coin,
+        }
+    }
 
-        // Moving platform
-        Platform::new(world, vec2(500.0, 500.0), PLATFORM_SIZE, true).await,
+    // Tint applied on top of the coin sprite
+    fn tint(&self) -> Color {
+        match self {
+            CoinTier::Common => WHITE,
+            CoinTier::Rare => Color::new(0.75, 0.85, 1.0, 1.0),  // Silvery blue
+            CoinTier::Gold => Color::new(1.0, 0.75, 0.3, 1.0),   // Deep gold
+        }
+    }
 
-        // Ground platform
-        Platform::new(world, vec2(0.0, 585.0), GROUND_SIZE, false).await,
-    ]
+    fn text_color(&self) -> Color {
+        match self {
+            CoinTier::Common => TEXT_ACCENT,
+            CoinTier::Rare => TEXT_SECONDARY,
+            CoinTier::Gold => TEXT_GOLD,
+        }
+    }
 }
 
+#[derive(Clone)]
 struct Coin {
     position: Vec2,
     lifetime: f32,
+    tier: CoinTier,
     texture: Texture2D,
 }
 
 impl Coin {
-    async fn new(position: Vec2) -> Self {
+    async fn new(position: Vec2, tier: CoinTier, lifetime: f32) -> Self {
         set_pc_assets_folder("assets");
         let texture = load_texture("player.png").await.expect("Couldn't load player texture");
         texture.set_filter(FilterMode::Nearest);
 
         Self {
             position,
-            lifetime: COIN_LIFETIME,
+            lifetime,
+            tier,
             texture,
         }
     }
 
-    fn update(&mut self) -> bool {
-        self.lifetime -= get_frame_time();
+    fn update(&mut self, dt: f32) -> bool {
+        self.lifetime -= dt;
         self.lifetime > 0.0  // Return true if coin is still alive
     }
 
@@ -872,7 +2858,7 @@ impl Coin {
                 &self.texture,
                 self.position.x,
                 self.position.y,
-                WHITE,
+                self.tier.tint(),
                 DrawTextureParams {
                     dest_size: Some(COIN_SIZE),
                     source: Some(Rect::new(
@@ -894,10 +2880,73 @@ impl Coin {
     }
 }
 
+// Floating "+points" text shown where a coin was picked up
+struct PopupText {
+    text: String,
+    position: Vec2,
+    timer: f32,
+    color: Color,
+}
+
+impl PopupText {
+    fn new(text: String, position: Vec2, color: Color) -> Self {
+        Self {
+            text,
+            position,
+            timer: POPUP_DURATION,
+            color,
+        }
+    }
+
+    fn update(&mut self, dt: f32) -> bool {
+        self.timer -= dt;
+        self.position.y -= POPUP_RISE_SPEED * dt;
+        self.timer > 0.0  // Return true while the popup is still visible
+    }
+
+    fn draw(&self) {
+        let mut color = self.color;
+        color.a = (self.timer / POPUP_DURATION).clamp(0.0, 1.0);
+        draw_text(&self.text, self.position.x, self.position.y, 20.0, color);
+    }
+}
+
+fn window_conf() -> Conf {
+    Settings::load().window_conf()
+}
+
+fn main() {
+    // The level check runs without opening a window
+    if std::env::args().any(|arg| arg == "--check-levels") {
+        let all_fine = validate::check_levels();
+        std::process::exit(if all_fine { 0 } else { 1 });
+    }
+    macroquad::Window::from_config(window_conf(), run());
+}
 
-#[macroquad::main("Chasedow")]
-async fn main() {
+async fn run() {
     let mut game = GameState::new().await;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--ghost" {
+            match args.next().map(|path| (Replay::load(&path), path)) {
+                Some((Ok(replay), path)) => {
+                    game.mode = replay.mode;
+                    game.ghost_replay = Some((path, replay));
+                }
+                Some((Err(e), _)) => warn!("{}", e),
+                None => warn!("--ghost needs a file"),
+            }
+        } else if arg == "--seed" {
+            game.seed_override = args.next().and_then(|seed| seed.parse().ok());
+        } else if arg == "--replay" {
+            match args.next().map(|path| Replay::load(&path)) {
+                Some(Ok(replay)) => game.start_playback(replay).await,
+                Some(Err(e)) => warn!("{}", e),
+                None => warn!("--replay needs a file"),
+            }
+        }
+    }
 
     loop {
         game.update().await;
@@ -0,0 +1,101 @@
+// Extra moves, enabled or disabled per game mode
+#[derive(Clone, Copy)]
+pub struct Abilities {
+    pub double_jump: bool,
+    pub wall_slide: bool,
+    pub dash: bool,
+}
+
+impl Default for Abilities {
+    fn default() -> Self {
+        Self {
+            double_jump: true,
+            wall_slide: true,
+            dash: true,
+        }
+    }
+}
+
+// What kind of run the player picked in the main menu
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Classic,    // One screen, survive as long as possible
+    Generated,  // Like Classic, on a new layout every run
+    Climber,    // Endless climb, the view scrolls up
+    Runner,     // Endless run, the view scrolls right
+    Daily,      // A generated layout with a few twists, the same for everyone on the same day
+    TimeAttack, // The level's flags (or a number of coins) against the clock
+    Versus,     // A second player steers the shadow, the runner has to outlast the timer
+}
+
+impl GameMode {
+    const ALL: [GameMode; 7] = [
+        GameMode::Classic,
+        GameMode::Generated,
+        GameMode::Climber,
+        GameMode::Runner,
+        GameMode::Daily,
+        GameMode::TimeAttack,
+        GameMode::Versus,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Generated => "Generated",
+            GameMode::Climber => "Climber",
+            GameMode::Runner => "Runner",
+            GameMode::Daily => "Daily",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Versus => "Versus",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    // What the score counts, for the HUD
+    pub fn score_label(&self) -> &'static str {
+        match self {
+            GameMode::Classic | GameMode::Generated | GameMode::Daily | GameMode::TimeAttack => "Score",
+            GameMode::Climber => "Height",
+            GameMode::Runner => "Distance",
+            GameMode::Versus => "Time",
+        }
+    }
+
+    // Played on the level's own layout, with its shade and coin zones
+    pub fn uses_level(&self) -> bool {
+        matches!(self, GameMode::Classic | GameMode::TimeAttack | GameMode::Versus)
+    }
+
+    // Co-op needs both players on one screen, and the daily and time attack are for one
+    pub fn allows_coop(&self) -> bool {
+        matches!(self, GameMode::Classic | GameMode::Generated)
+    }
+
+    // The climb is about going up, so no dash. The runner pushes you along, so no clinging to walls
+    pub fn abilities(&self) -> Abilities {
+        match self {
+            GameMode::Climber => Abilities { dash: false, ..Abilities::default() },
+            GameMode::Runner => Abilities { wall_slide: false, ..Abilities::default() },
+            _ => Abilities::default(),
+        }
+    }
+
+    // Rewinding would hand the time attack's clock back the coins already counted
+    pub fn allows_rewind(&self) -> bool {
+        !matches!(self, GameMode::TimeAttack)
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
@@ -0,0 +1,244 @@
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+
+use crate::config::parse_entries;
+use crate::mix_color;
+
+// Emitters are described in a data file, with the build-time copy as a fallback
+const PARTICLES_PATH: &str = "assets/particles.txt";
+const EMBEDDED_PARTICLES: &str = include_str!("../assets/particles.txt");
+const MAX_PARTICLES: usize = 512;  // Size of the pool, new particles are dropped once it's full
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Effect {
+    Dust,
+    Sparkle,
+    Hit,
+    ShadowSmoke,
+}
+
+impl Effect {
+    const ALL: [Effect; 4] = [Effect::Dust, Effect::Sparkle, Effect::Hit, Effect::ShadowSmoke];
+
+    fn section(&self) -> &'static str {
+        match self {
+            Effect::Dust => "dust",
+            Effect::Sparkle => "sparkle",
+            Effect::Hit => "hit",
+            Effect::ShadowSmoke => "shadow_smoke",
+        }
+    }
+}
+
+// How one kind of effect spawns its particles
+#[derive(Clone, Copy)]
+struct EmitterDef {
+    count: u32,         // Particles per burst
+    rate: f32,          // Particles per second for continuous emitters
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    angle: (f32, f32),  // Degrees, 0 = right, -90 = up
+    spread: f32,        // Random offset from the emit position
+    gravity: f32,
+    size: (f32, f32),   // Start and end size
+    color_start: Color,
+    color_end: Color,
+}
+
+impl Default for EmitterDef {
+    fn default() -> Self {
+        Self {
+            count: 8,
+            rate: 0.0,
+            lifetime: (0.3, 0.6),
+            speed: (20.0, 60.0),
+            angle: (0.0, 360.0),
+            spread: 0.0,
+            gravity: 0.0,
+            size: (4.0, 0.0),
+            color_start: WHITE,
+            color_end: Color::new(1.0, 1.0, 1.0, 0.0),
+        }
+    }
+}
+
+impl EmitterDef {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let numbers = value
+            .split_whitespace()
+            .map(|n| n.parse::<f32>().map_err(|e| e.to_string()))
+            .collect::<Result<Vec<f32>, String>>()?;
+        let pair = || match numbers[..] {
+            [a] => Ok((a, a)),
+            [a, b] => Ok((a, b)),
+            _ => Err(format!("`{}` expects one or two numbers", key)),
+        };
+        let color = || match numbers[..] {
+            [r, g, b, a] => Ok(Color::new(r, g, b, a)),
+            _ => Err(format!("`{}` expects four numbers (r g b a)", key)),
+        };
+        let single = || numbers.first().copied().ok_or(format!("`{}` needs a value", key));
+
+        match key {
+            "count" => self.count = single()? as u32,
+            "rate" => self.rate = single()?,
+            "lifetime" => {
+                let lifetime = pair()?;
+                if lifetime.0 <= 0.0 || lifetime.1 <= 0.0 {
+                    return Err(format!("`{}` must be above zero", key));
+                }
+                self.lifetime = lifetime;
+            }
+            "speed" => self.speed = pair()?,
+            "angle" => self.angle = pair()?,
+            "spread" => self.spread = single()?,
+            "gravity" => self.gravity = single()?,
+            "size" => self.size = pair()?,
+            "color_start" => self.color_start = color()?,
+            "color_end" => self.color_end = color()?,
+            _ => return Err(format!("unknown key `{}`", key)),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Particle {
+    alive: bool,
+    position: Vec2,
+    velocity: Vec2,
+    life: f32,
+    max_life: f32,
+    gravity: f32,
+    size: (f32, f32),
+    color_start: Color,
+    color_end: Color,
+}
+
+impl Particle {
+    const DEAD: Particle = Particle {
+        alive: false,
+        position: Vec2::ZERO,
+        velocity: Vec2::ZERO,
+        life: 0.0,
+        max_life: 1.0,
+        gravity: 0.0,
+        size: (0.0, 0.0),
+        color_start: WHITE,
+        color_end: WHITE,
+    };
+}
+
+pub struct ParticleSystem {
+    defs: Vec<(Effect, EmitterDef)>,
+    pool: Vec<Particle>,
+    trail_accumulator: f32,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        let text = std::fs::read_to_string(PARTICLES_PATH).unwrap_or_else(|_| EMBEDDED_PARTICLES.to_string());
+
+        let mut defs: Vec<(Effect, EmitterDef)> = Effect::ALL.iter().map(|e| (*e, EmitterDef::default())).collect();
+        for entry in parse_entries(&text) {
+            let Some((_, def)) = defs.iter_mut().find(|(e, _)| e.section() == entry.section) else {
+                warn!("particles line {}: unknown emitter `{}`", entry.line, entry.section);
+                continue;
+            };
+            if let Err(e) = def.set(entry.key, entry.value) {
+                warn!("particles line {}: {}", entry.line, e);
+            }
+        }
+
+        Self {
+            defs,
+            pool: vec![Particle::DEAD; MAX_PARTICLES],
+            trail_accumulator: 0.0,
+        }
+    }
+
+    fn def(&self, effect: Effect) -> EmitterDef {
+        self.defs
+            .iter()
+            .find(|(e, _)| *e == effect)
+            .map(|(_, def)| *def)
+            .unwrap_or_default()
+    }
+
+    // One-off burst, e.g. a coin pickup
+    pub fn emit(&mut self, effect: Effect, position: Vec2) {
+        let def = self.def(effect);
+        for _ in 0..def.count {
+            self.spawn(&def, position);
+        }
+    }
+
+    // Continuous emitter following something around, e.g. the shadow's smoke
+    pub fn emit_trail(&mut self, effect: Effect, position: Vec2, dt: f32) {
+        let def = self.def(effect);
+        self.trail_accumulator += def.rate * dt;
+        while self.trail_accumulator >= 1.0 {
+            self.trail_accumulator -= 1.0;
+            self.spawn(&def, position);
+        }
+    }
+
+    fn spawn(&mut self, def: &EmitterDef, position: Vec2) {
+        // Reuse a dead slot, or skip the particle if the pool is exhausted
+        let Some(slot) = self.pool.iter_mut().find(|p| !p.alive) else {
+            return;
+        };
+
+        let angle = gen_range(def.angle.0, def.angle.1).to_radians();
+        let speed = gen_range(def.speed.0, def.speed.1);
+        let offset = vec2(gen_range(-def.spread, def.spread), gen_range(-def.spread, def.spread));
+        let life = gen_range(def.lifetime.0, def.lifetime.1);
+
+        *slot = Particle {
+            alive: true,
+            position: position + offset,
+            velocity: vec2(angle.cos(), angle.sin()) * speed,
+            life,
+            max_life: life,
+            gravity: def.gravity,
+            size: def.size,
+            color_start: def.color_start,
+            color_end: def.color_end,
+        };
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for particle in self.pool.iter_mut().filter(|p| p.alive) {
+            particle.life -= dt;
+            if particle.life <= 0.0 {
+                particle.alive = false;
+                continue;
+            }
+            particle.velocity.y += particle.gravity * dt;
+            particle.position += particle.velocity * dt;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for particle in self.pool.iter_mut() {
+            particle.alive = false;
+        }
+        self.trail_accumulator = 0.0;
+    }
+
+    pub fn draw(&self) {
+        for particle in self.pool.iter().filter(|p| p.alive) {
+            // 0.0 when just spawned, 1.0 when about to die
+            let t = 1.0 - particle.life / particle.max_life;
+            let size = particle.size.0 + (particle.size.1 - particle.size.0) * t;
+            let color = mix_color(particle.color_start, particle.color_end, t);
+            draw_rectangle(
+                particle.position.x - size * 0.5,
+                particle.position.y - size * 0.5,
+                size,
+                size,
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use macroquad::prelude::*;
+
+use crate::config::{parse_entries, Preset};
+use crate::input::Controls;
+use crate::mode::GameMode;
+
+// A run written down frame by frame. The seed fixes everything random, so the controls and
+// the frame times are enough to play it again exactly. Positions are kept to notice when it doesn't
+pub const REPLAYS_FOLDER: &str = "replays";
+const SYNC_TOLERANCE: f32 = 0.01;  // Pixels a played back position may be off by
+
+#[derive(Clone, Copy)]
+pub struct ReplayFrame {
+    pub dt: f32,
+    pub controls: Controls,
+    pub position: Vec2,  // Where the player was at the start of the frame
+}
+
+#[derive(Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub preset: Preset,
+    pub config: u64,    // Fingerprint of the config the run was played with
+    pub label: String,  // e.g. the date of a daily challenge
+    pub score: f32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, mode: GameMode, preset: Preset, config: u64) -> Self {
+        Self {
+            seed,
+            mode,
+            preset,
+            config,
+            label: String::new(),
+            score: 0.0,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+        let mut replay = Self::new(0, GameMode::Classic, Preset::Normal, 0);
+        for entry in parse_entries(&text) {
+            replay
+                .set(entry.key, entry.value)
+                .map_err(|e| format!("{} line {}: {}", path, entry.line, e))?;
+        }
+        Ok(replay)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "seed" => self.seed = value.parse().map_err(|_| "bad seed".to_string())?,
+            "mode" => self.mode = GameMode::from_name(value).ok_or(format!("unknown mode `{}`", value))?,
+            "preset" => self.preset = Preset::from_name(value).ok_or(format!("unknown preset `{}`", value))?,
+            "config" => self.config = u64::from_str_radix(value, 16).map_err(|_| "bad config fingerprint".to_string())?,
+            "label" => self.label = value.to_string(),
+            "score" => self.score = value.parse().map_err(|_| "bad score".to_string())?,
+            "frame" => {
+                let parts: Vec<&str> = value.split_whitespace().collect();
+                let [dt, bits, x, y] = parts[..] else {
+                    return Err("`frame` expects dt controls x y".to_string());
+                };
+                let number = |n: &str| n.parse::<f32>().map_err(|e| e.to_string());
+                self.frames.push(ReplayFrame {
+                    dt: number(dt)?,
+                    controls: Controls::from_bits(bits.parse().map_err(|_| "bad controls".to_string())?),
+                    position: vec2(number(x)?, number(y)?),
+                });
+            }
+            _ => return Err(format!("unknown key `{}`", key)),
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut text = format!(
+            "# Chasedow replay, watch it with `--replay {}`\nseed = {}\nmode = {}\npreset = {}\nconfig = {:016x}\nlabel = {}\nscore = {:.2}\n",
+            path,
+            self.seed,
+            self.mode.name(),
+            self.preset.name(),
+            self.config,
+            self.label,
+            self.score
+        );
+        // Rust prints floats so they read back exactly, which the frame times rely on
+        for frame in &self.frames {
+            text.push_str(&format!(
+                "frame = {} {} {} {}\n",
+                frame.dt,
+                frame.controls.to_bits(),
+                frame.position.x,
+                frame.position.y
+            ));
+        }
+
+        std::fs::create_dir_all(REPLAYS_FOLDER).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+}
+
+// Every replay in the replays folder with its path, sorted by file name
+pub fn list_replays() -> Vec<(String, Replay)> {
+    let Ok(entries) = std::fs::read_dir(REPLAYS_FOLDER) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| match Replay::load(&path) {
+            Ok(replay) => Some((path, replay)),
+            Err(e) => {
+                warn!("{}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+// Feeds a replay back into the game one frame at a time
+pub struct Playback {
+    pub replay: Replay,
+    next: usize,
+    in_sync: bool,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0, in_sync: true }
+    }
+
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        let frame = self.replay.frames.get(self.next).copied();
+        self.next += 1;
+        frame
+    }
+
+    // Compare with where the player was when the run was recorded, warning once if they drift apart
+    pub fn check_sync(&mut self, frame: &ReplayFrame, position: Vec2) {
+        if self.in_sync && frame.position.distance(position) > SYNC_TOLERANCE {
+            self.in_sync = false;
+            warn!("replay out of sync at frame {}", self.next);
+        }
+    }
+}
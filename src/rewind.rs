@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+
+// Rewind power: a few seconds of snapshots, played back while the key is held
+const HISTORY_SECONDS: f32 = 3.0;   // How far back the history goes
+const MAX_CHARGE: f32 = 3.0;        // Seconds of rewind a full bar holds
+const CHARGE_PER_POINT: f32 = 0.02; // Coins fill the bar, 50 points for a full second
+
+pub struct Rewind<T> {
+    history: VecDeque<(f32, T)>,  // Each snapshot with the game time of its frame
+    recorded: f32,                // Game time covered by the history
+    charge: f32,                  // Seconds of rewind available
+    earned: f32,                  // All the charge coins ever added, so a rewind can take it back
+}
+
+impl<T> Rewind<T> {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::new(),
+            recorded: 0.0,
+            charge: 0.0,
+            earned: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.recorded = 0.0;
+        self.charge = 0.0;
+        self.earned = 0.0;
+    }
+
+    // When the past can't be put back any more, but the charge stays
+    pub fn forget_history(&mut self) {
+        self.history.clear();
+        self.recorded = 0.0;
+    }
+
+    pub fn record(&mut self, dt: f32, snapshot: T) {
+        self.history.push_back((dt, snapshot));
+        self.recorded += dt;
+
+        // Drop whatever is too old to ever be rewound to
+        while self.recorded > HISTORY_SECONDS {
+            let Some((oldest_dt, _)) = self.history.pop_front() else {
+                break;
+            };
+            self.recorded -= oldest_dt;
+        }
+    }
+
+    pub fn add_charge(&mut self, points: i32) {
+        let charge = (self.charge + points as f32 * CHARGE_PER_POINT).min(MAX_CHARGE);
+        self.earned += charge - self.charge;
+        self.charge = charge;
+    }
+
+    pub fn earned(&self) -> f32 {
+        self.earned
+    }
+
+    // Rewinding past a pickup gives the coin back, so the charge it brought goes too
+    pub fn take_back_since(&mut self, earned: f32) {
+        self.charge = (self.charge - (self.earned - earned).max(0.0)).max(0.0);
+        self.earned = self.earned.min(earned);
+    }
+
+    pub fn can_rewind(&self) -> bool {
+        self.charge > 0.0 && !self.history.is_empty()
+    }
+
+    // Step one frame back in time, spending charge for the game time it undoes
+    pub fn step_back(&mut self) -> Option<T> {
+        if !self.can_rewind() {
+            return None;
+        }
+        let (dt, snapshot) = self.history.pop_back()?;
+        self.recorded -= dt;
+        self.charge = (self.charge - dt).max(0.0);
+        Some(snapshot)
+    }
+
+    // 0.0 to 1.0, for the HUD
+    pub fn charge_fraction(&self) -> f32 {
+        self.charge / MAX_CHARGE
+    }
+}
@@ -0,0 +1,49 @@
+// Deterministic random numbers for anything that has to come out the same from the same seed.
+// The global generator is shared with things like particles, so it can't be relied on for that
+#[derive(Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // A generator of its own for one use of a seed, so e.g. coins don't follow the layout's numbers
+    pub fn with_stream(seed: u64, stream: &str) -> Self {
+        Self::new(seed ^ hash_text(stream))
+    }
+
+    // SplitMix64: tiny, fast and good enough for level generation
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in `low..high`
+    pub fn gen_range(&mut self, low: f32, high: f32) -> f32 {
+        let unit = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        low + (high - low) * unit
+    }
+
+    // Index into a collection of `len` items
+    pub fn gen_index(&mut self, len: usize) -> usize {
+        (self.next_u64() % len.max(1) as u64) as usize
+    }
+}
+
+// FNV-1a: the same number for the same text on every machine and every build
+pub fn hash_text(text: &str) -> u64 {
+    text.bytes().fold(0xCBF2_9CE4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+// A seed that changes every run
+pub fn random_seed() -> u64 {
+    (macroquad::miniquad::date::now() * 1000.0) as u64
+}
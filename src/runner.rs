@@ -0,0 +1,240 @@
+use macroquad::prelude::*;
+
+use crate::config::parse_entries;
+use crate::rng::SeededRng;
+
+// The runner's track is stitched together from hand-authored chunks picked from a seed
+const CHUNKS_PATH: &str = "assets/chunks.txt";
+const EMBEDDED_CHUNKS: &str = include_str!("../assets/chunks.txt");
+const START_CHUNK: &str = "start";          // Always first, so the run begins on solid ground
+const DIFFICULTY_STEP: f32 = 2500.0;        // Pixels of track between two difficulty levels
+const MAX_DIFFICULTY: u32 = 3;
+
+// Chunk coordinates are relative to the chunk's left edge, heights as on the classic screen
+#[derive(Clone)]
+pub struct ChunkPlatform {
+    pub pos: Vec2,
+    pub width: f32,
+    pub speed: f32,            // 0.0 for static platforms
+    pub travel: f32,           // How far right of `pos` a moving platform goes before turning back
+    pub cacti: Vec<(f32, f32)>, // Offset from the platform's left edge and size
+}
+
+#[derive(Clone)]
+pub struct ChunkCoin {
+    pub pos: Vec2,
+    pub tier: String,
+}
+
+#[derive(Clone)]
+pub struct Chunk {
+    pub name: String,
+    pub width: f32,
+    pub difficulty: u32,
+    pub platforms: Vec<ChunkPlatform>,
+    pub coins: Vec<ChunkCoin>,
+}
+
+impl Chunk {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            width: 0.0,
+            difficulty: 0,
+            platforms: Vec::new(),
+            coins: Vec::new(),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        // A coin can end with the name of its tier, everything else is numbers only
+        let tokens: Vec<&str> = value.split_whitespace().collect();
+        let (numeric, tier) = match (key, &tokens[..]) {
+            ("coin", [x, y, tier]) => (vec![*x, *y], Some(*tier)),
+            _ => (tokens, None),
+        };
+        let numbers = numeric
+            .iter()
+            .map(|n| n.parse::<f32>().map_err(|_| format!("`{}` isn't a number", n)))
+            .collect::<Result<Vec<f32>, String>>()?;
+        match (key, &numbers[..]) {
+            ("width", [width]) => self.width = *width,
+            ("difficulty", [difficulty]) => self.difficulty = *difficulty as u32,
+            ("platform", [x, y, width]) => self.platforms.push(ChunkPlatform {
+                pos: vec2(*x, *y),
+                width: *width,
+                speed: 0.0,
+                travel: 0.0,
+                cacti: Vec::new(),
+            }),
+            ("platform", [x, y, width, speed, travel]) => self.platforms.push(ChunkPlatform {
+                pos: vec2(*x, *y),
+                width: *width,
+                speed: *speed,
+                travel: *travel,
+                cacti: Vec::new(),
+            }),
+            ("cactus", [x, y, size]) => {
+                // Cacti stand on whichever static platform is under them
+                let platform = self
+                    .platforms
+                    .iter_mut()
+                    .find(|p| p.speed == 0.0 && (p.pos.y - y).abs() < 1.0 && *x >= p.pos.x && x + size <= p.pos.x + p.width)
+                    .ok_or("cactus isn't standing on a static platform")?;
+                platform.cacti.push((x - platform.pos.x, *size));
+            }
+            ("coin", [x, y]) => self.coins.push(ChunkCoin {
+                pos: vec2(*x, *y),
+                tier: tier.unwrap_or("common").to_string(),
+            }),
+            _ => return Err(format!("can't read `{} = {}`", key, value)),
+        }
+        Ok(())
+    }
+
+    // A copy moved to where it goes on the track
+    fn placed_at(&self, x: f32) -> Chunk {
+        let mut chunk = self.clone();
+        for platform in chunk.platforms.iter_mut() {
+            platform.pos.x += x;
+        }
+        for coin in chunk.coins.iter_mut() {
+            coin.pos.x += x;
+        }
+        chunk
+    }
+}
+
+pub fn load_chunks() -> Vec<Chunk> {
+    let text = std::fs::read_to_string(CHUNKS_PATH).unwrap_or_else(|_| EMBEDDED_CHUNKS.to_string());
+
+    let mut chunks: Vec<Chunk> = Vec::new();
+    for entry in parse_entries(&text) {
+        if chunks.last().map(|chunk| chunk.name != entry.section).unwrap_or(true) {
+            chunks.push(Chunk::new(&entry.section));
+        }
+        let chunk = chunks.last_mut().unwrap();
+        if let Err(e) = chunk.set(entry.key, entry.value) {
+            warn!("chunks line {}: {}", entry.line, e);
+        }
+    }
+
+    chunks.retain(|chunk| {
+        if chunk.width <= 0.0 {
+            warn!("chunk `{}` has no width, skipping it", chunk.name);
+        }
+        chunk.width > 0.0
+    });
+    chunks
+}
+
+pub struct RunnerTrack {
+    chunks: Vec<Chunk>,
+    rng: SeededRng,
+    next_x: f32,           // Where the next chunk starts
+    last: Option<usize>,   // Never the same chunk twice in a row
+}
+
+impl RunnerTrack {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            chunks: load_chunks(),
+            rng: SeededRng::new(seed),
+            next_x: 0.0,
+            last: None,
+        }
+    }
+
+    // Harder chunks unlock the further the track goes
+    fn difficulty(&self) -> u32 {
+        ((self.next_x / DIFFICULTY_STEP) as u32).min(MAX_DIFFICULTY)
+    }
+
+    fn pick(&mut self) -> Option<usize> {
+        if self.next_x == 0.0 {
+            if let Some(start) = self.chunks.iter().position(|chunk| chunk.name == START_CHUNK) {
+                return Some(start);
+            }
+        }
+
+        let difficulty = self.difficulty();
+        let candidates: Vec<usize> = (0..self.chunks.len())
+            .filter(|i| self.chunks[*i].name != START_CHUNK && self.chunks[*i].difficulty <= difficulty)
+            .filter(|i| Some(*i) != self.last)
+            .collect();
+        if candidates.is_empty() {
+            return self.last;
+        }
+        Some(candidates[self.rng.gen_index(candidates.len())])
+    }
+
+    // The chunks needed for the track to reach `x`, already placed in the world
+    pub fn extend_to(&mut self, x: f32) -> Vec<Chunk> {
+        let mut placed = Vec::new();
+        while self.next_x < x {
+            let Some(index) = self.pick() else {
+                break;
+            };
+            placed.push(self.chunks[index].placed_at(self.next_x));
+            self.next_x += self.chunks[index].width;
+            self.last = Some(index);
+        }
+        placed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(lines: &[(&str, &str)]) -> Result<Chunk, String> {
+        let mut chunk = Chunk::new("test");
+        for (key, value) in lines {
+            chunk.set(key, value)?;
+        }
+        Ok(chunk)
+    }
+
+    #[test]
+    fn reads_a_chunk() {
+        let chunk = chunk(&[
+            ("width", "400"),
+            ("platform", "0 585 200"),
+            ("platform", "240 480 100 40 120"),
+            ("cactus", "60 585 36"),
+            ("coin", "250 420 gold"),
+            ("coin", "100 520"),
+        ])
+        .unwrap();
+        assert_eq!(chunk.width, 400.0);
+        assert_eq!(chunk.platforms.len(), 2);
+        assert_eq!(chunk.platforms[1].travel, 120.0);
+        assert_eq!(chunk.platforms[0].cacti, vec![(60.0, 36.0)]);
+        assert_eq!(chunk.coins[0].tier, "gold");
+        assert_eq!(chunk.coins[1].tier, "common");
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        assert!(chunk(&[("platform", "0 585 200 oops")]).is_err());
+        assert!(chunk(&[("width", "400 400")]).is_err());
+        assert!(chunk(&[("coin", "100 oops gold")]).is_err());
+    }
+
+    #[test]
+    fn rejects_a_floating_cactus() {
+        assert!(chunk(&[("platform", "0 585 200"), ("cactus", "60 480 36")]).is_err());
+        assert!(chunk(&[("platform", "0 585 200 40 120"), ("cactus", "60 585 36")]).is_err());
+    }
+
+    #[test]
+    fn same_seed_same_track() {
+        let names = |seed| -> Vec<String> {
+            RunnerTrack::new(seed).extend_to(20000.0).into_iter().map(|chunk| chunk.name).collect()
+        };
+        let track = names(7);
+        assert_eq!(track[0], START_CHUNK);
+        assert_eq!(track, names(7));
+        assert!(track.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+}
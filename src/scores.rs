@@ -0,0 +1,133 @@
+use crate::config::Preset;
+use crate::mode::GameMode;
+
+// High scores are kept next to the game, one `score difficulty adaptive|fixed game-mode` entry per line
+const SCORES_PATH: &str = "highscores.txt";
+const MAX_ENTRIES_PER_DIFFICULTY: usize = 5;
+
+#[derive(Clone, Copy)]
+pub struct HighScore {
+    pub score: f32,
+    pub preset: Preset,
+    pub adaptive: bool,
+    pub mode: GameMode,
+}
+
+impl HighScore {
+    // Entries only compete with runs of the same mode and difficulty
+    fn same_table(&self, other: &HighScore) -> bool {
+        self.mode == other.mode && self.preset == other.preset && self.adaptive == other.adaptive
+    }
+}
+
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        let text = std::fs::read_to_string(SCORES_PATH).unwrap_or_default();
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let score = parts.next()?.parse().ok()?;
+                let preset = Preset::from_name(parts.next()?)?;
+                let adaptive = parts.next()? == "adaptive";
+                // Scores saved before there were game modes are all Classic
+                let mode = parts.next().map_or(Some(GameMode::Classic), GameMode::from_name)?;
+                Some(HighScore { score, preset, adaptive, mode })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    fn save(&self) {
+        let text: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let adaptive = if entry.adaptive { "adaptive" } else { "fixed" };
+                format!("{:.2} {} {} {}\n", entry.score, entry.preset.name(), adaptive, entry.mode.name())
+            })
+            .collect();
+
+        // Not being able to save (e.g. on the web) shouldn't stop the game
+        let _ = std::fs::write(SCORES_PATH, text);
+    }
+
+    // Best score for a mode and difficulty; adaptive runs are ranked separately
+    pub fn best(&self, mode: GameMode, preset: Preset, adaptive: bool) -> f32 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.mode == mode && entry.preset == preset && entry.adaptive == adaptive)
+            .map(|entry| entry.score)
+            .fold(0.0, f32::max)
+    }
+
+    // Returns true if this is a new best for its difficulty
+    pub fn submit(&mut self, entry: HighScore) -> bool {
+        let is_best = self.insert(entry);
+        self.save();
+        is_best
+    }
+
+    fn insert(&mut self, entry: HighScore) -> bool {
+        let is_best = entry.score > self.best(entry.mode, entry.preset, entry.adaptive);
+
+        self.entries.push(entry);
+        self.entries.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        // Keep the top few of each mode and difficulty so easy runs never push out hard ones
+        let mut kept: Vec<HighScore> = Vec::new();
+        for entry in self.entries.drain(..) {
+            let same_difficulty = kept.iter().filter(|k| k.same_table(&entry)).count();
+            if same_difficulty < MAX_ENTRIES_PER_DIFFICULTY {
+                kept.push(entry);
+            }
+        }
+        self.entries = kept;
+
+        is_best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(score: f32, preset: Preset) -> HighScore {
+        HighScore { score, preset, adaptive: false, mode: GameMode::Classic }
+    }
+
+    fn scores(table: &HighScores, preset: Preset) -> Vec<f32> {
+        table.entries.iter().filter(|entry| entry.preset == preset).map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn ranks_each_difficulty_on_its_own() {
+        let mut table = HighScores { entries: Vec::new() };
+        assert!(table.insert(run(10.0, Preset::Hard)));
+        assert!(table.insert(run(50.0, Preset::Easy)));
+        assert!(!table.insert(run(5.0, Preset::Hard)));
+        assert!(table.insert(run(20.0, Preset::Hard)));
+
+        assert_eq!(table.best(GameMode::Classic, Preset::Hard, false), 20.0);
+        assert_eq!(table.best(GameMode::Classic, Preset::Easy, false), 50.0);
+        assert_eq!(table.best(GameMode::Classic, Preset::Hard, true), 0.0);
+        assert_eq!(table.best(GameMode::Climber, Preset::Hard, false), 0.0);
+    }
+
+    #[test]
+    fn trims_each_difficulty_to_the_best_few() {
+        let mut table = HighScores { entries: Vec::new() };
+        table.insert(run(1.0, Preset::Hard));
+        for score in 10..20 {
+            table.insert(run(score as f32, Preset::Easy));
+        }
+        assert_eq!(scores(&table, Preset::Easy), vec![19.0, 18.0, 17.0, 16.0, 15.0]);
+        // Easy runs never push a hard one out
+        assert_eq!(scores(&table, Preset::Hard), vec![1.0]);
+    }
+}
@@ -0,0 +1,107 @@
+use macroquad::miniquad::conf::Platform;
+use macroquad::prelude::*;
+use macroquad::window::Conf;
+
+use crate::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// Window and display options, saved next to the game as `key = value` lines
+const SETTINGS_PATH: &str = "settings.txt";
+
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub fullscreen: bool,
+    pub window_width: i32,
+    pub window_height: i32,
+    pub high_dpi: bool,
+    pub vsync: bool,  // Only read at startup
+    pub integer_scaling: bool,
+    pub reduce_motion: bool,   // Turns off shake, hit-stop, slow motion and wobble
+    pub screen_shake: f32,     // 0.0 to 1.0
+    pub hit_stop_frames: u32,
+    pub slow_motion: f32,      // Game speed during slow motion
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            window_width: WINDOW_WIDTH as i32,
+            window_height: WINDOW_HEIGHT as i32,
+            high_dpi: false,
+            vsync: true,
+            integer_scaling: false,
+            reduce_motion: false,
+            screen_shake: 1.0,
+            hit_stop_frames: 4,
+            slow_motion: 0.25,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+        let text = std::fs::read_to_string(SETTINGS_PATH).unwrap_or_default();
+
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let flag = value == "true";
+            let size = value.parse::<i32>().ok().filter(|size| *size > 0);
+            let amount = value.parse::<f32>().ok().map(|amount| amount.clamp(0.0, 1.0));
+
+            match key.trim() {
+                "fullscreen" => settings.fullscreen = flag,
+                "window_width" => settings.window_width = size.unwrap_or(settings.window_width),
+                "window_height" => settings.window_height = size.unwrap_or(settings.window_height),
+                "high_dpi" => settings.high_dpi = flag,
+                "vsync" => settings.vsync = flag,
+                "integer_scaling" => settings.integer_scaling = flag,
+                "reduce_motion" => settings.reduce_motion = flag,
+                "screen_shake" => settings.screen_shake = amount.unwrap_or(settings.screen_shake),
+                "hit_stop_frames" => settings.hit_stop_frames = value.parse().unwrap_or(settings.hit_stop_frames),
+                "slow_motion" => settings.slow_motion = amount.unwrap_or(settings.slow_motion),
+                _ => warn!("unknown setting `{}`", key.trim()),
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self) {
+        let text = format!(
+            "fullscreen = {}\nwindow_width = {}\nwindow_height = {}\nhigh_dpi = {}\nvsync = {}\ninteger_scaling = {}\n\
+             reduce_motion = {}\nscreen_shake = {}\nhit_stop_frames = {}\nslow_motion = {}\n",
+            self.fullscreen,
+            self.window_width,
+            self.window_height,
+            self.high_dpi,
+            self.vsync,
+            self.integer_scaling,
+            self.reduce_motion,
+            self.screen_shake,
+            self.hit_stop_frames,
+            self.slow_motion,
+        );
+        // Not being able to save (e.g. on the web) shouldn't stop the game
+        let _ = std::fs::write(SETTINGS_PATH, text);
+    }
+
+    pub fn window_conf(&self) -> Conf {
+        Conf {
+            window_title: "Chasedow".to_owned(),
+            window_width: self.window_width,
+            window_height: self.window_height,
+            high_dpi: self.high_dpi,
+            fullscreen: self.fullscreen,
+            window_resizable: true,
+            platform: Platform {
+                swap_interval: Some(if self.vsync { 1 } else { 0 }),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use macroquad::prelude::*;
+
+use crate::config::Preset;
+use crate::level::Level;
+use crate::{TEXT_ACCENT, TEXT_SECONDARY};
+
+// Time attack: an objective against the clock. The clock counts simulation ticks, which run at a
+// fixed rate whatever the frame rate, so a time is exact and means the same on every machine
+pub const TICK_RATE: u32 = 60;
+pub const TICK: f32 = 1.0 / TICK_RATE as f32;
+pub const MAX_TICKS_PER_FRAME: u32 = 5;   // Don't try to catch up on more than this after a stall
+const SPLITS_PATH: &str = "splits.txt";
+const COIN_GOAL: u32 = 20;        // Levels without flags are about collecting coins
+const COINS_PER_SPLIT: u32 = 5;
+const FLAG_POLE_WIDTH: f32 = 3.0;
+const FLAG_COLOR: Color = Color::new(0.8, 0.25, 0.2, 1.0);
+const FLAG_DONE_COLOR: Color = Color::new(0.8, 0.25, 0.2, 0.25);
+
+enum Objective {
+    Coins,
+    Flags(Vec<Rect>),  // Reached in order, the last one finishes the run
+}
+
+pub struct TimeAttack {
+    objective: Objective,
+    pub ticks: u32,
+    pub splits: Vec<u32>,    // Tick of each split reached so far
+    pub best: Option<Vec<u32>>,  // Personal best splits, the last one is the final time
+    coins: u32,
+}
+
+impl TimeAttack {
+    // The level's exits are its flags
+    pub fn new(level: &Level, best: Option<Vec<u32>>) -> Self {
+        let objective = if level.exits.is_empty() { Objective::Coins } else { Objective::Flags(level.exits.clone()) };
+        let mut time_attack = Self {
+            objective,
+            ticks: 0,
+            splits: Vec::new(),
+            best: None,
+            coins: 0,
+        };
+        // Splits from before the level was edited don't compare
+        time_attack.best = best.filter(|best| best.len() == time_attack.split_count());
+        time_attack
+    }
+
+    pub fn split_count(&self) -> usize {
+        match &self.objective {
+            Objective::Coins => (COIN_GOAL / COINS_PER_SPLIT) as usize,
+            Objective::Flags(flags) => flags.len(),
+        }
+    }
+
+    pub fn split_name(&self, index: usize) -> String {
+        match self.objective {
+            Objective::Coins => format!("{} coins", (index as u32 + 1) * COINS_PER_SPLIT),
+            Objective::Flags(_) => format!("Flag {}", index + 1),
+        }
+    }
+
+    // What's left to do, for the HUD
+    pub fn goal_text(&self) -> String {
+        match &self.objective {
+            Objective::Coins => format!("Coins: {}/{}", self.coins, COIN_GOAL),
+            Objective::Flags(flags) => format!("Flags: {}/{}", self.splits.len(), flags.len()),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.splits.len() >= self.split_count()
+    }
+
+    // One simulation tick. Returns true on the tick the run is finished
+    pub fn tick(&mut self, player: Rect) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        self.ticks += 1;
+
+        if let Objective::Flags(flags) = &self.objective {
+            if flags[self.splits.len()].overlaps(&player) {
+                self.splits.push(self.ticks);
+            }
+        }
+        self.is_finished()
+    }
+
+    pub fn collect_coin(&mut self) {
+        if let Objective::Coins = self.objective {
+            self.coins += 1;
+            if self.coins % COINS_PER_SPLIT == 0 && !self.is_finished() {
+                self.splits.push(self.ticks);
+            }
+        }
+    }
+
+    // Ticks ahead (negative) or behind the personal best at a split
+    pub fn delta(&self, index: usize) -> Option<i64> {
+        let best = self.best.as_ref()?.get(index)?;
+        Some(*self.splits.get(index)? as i64 - *best as i64)
+    }
+
+    // Once the clock passes the personal best for the next split, the player is behind already
+    pub fn live_delta(&self) -> Option<i64> {
+        let index = self.splits.len();
+        let best = *self.best.as_ref()?.get(index)?;
+        (self.ticks > best).then(|| self.ticks as i64 - best as i64)
+    }
+
+    pub fn final_ticks(&self) -> Option<u32> {
+        self.splits.last().copied().filter(|_| self.is_finished())
+    }
+
+    // Flags still to reach, the next one brightest
+    pub fn draw_flags(&self) {
+        let Objective::Flags(flags) = &self.objective else {
+            return;
+        };
+        for (i, flag) in flags.iter().enumerate().skip(self.splits.len()) {
+            let color = if i == self.splits.len() { FLAG_COLOR } else { FLAG_DONE_COLOR };
+            draw_rectangle(flag.x, flag.y, FLAG_POLE_WIDTH, flag.h, TEXT_SECONDARY);
+            draw_triangle(
+                vec2(flag.x + FLAG_POLE_WIDTH, flag.y),
+                vec2(flag.x + FLAG_POLE_WIDTH, flag.y + flag.h * 0.4),
+                vec2(flag.right(), flag.y + flag.h * 0.2),
+                color,
+            );
+            draw_text(&(i + 1).to_string(), flag.x + 6.0, flag.y + flag.h - 4.0, 16.0, TEXT_ACCENT);
+        }
+    }
+}
+
+// 1:02.35
+pub fn format_ticks(ticks: u32) -> String {
+    let hundredths = ticks as u64 * 100 / TICK_RATE as u64;
+    format!("{}:{:02}.{:02}", hundredths / 6000, hundredths / 100 % 60, hundredths % 100)
+}
+
+// +0.52 or -1.20
+pub fn format_delta(ticks: i64) -> String {
+    let sign = if ticks < 0 { '-' } else { '+' };
+    let hundredths = ticks.unsigned_abs() * 100 / TICK_RATE as u64;
+    format!("{}{}.{:02}", sign, hundredths / 100, hundredths % 100)
+}
+
+struct SplitEntry {
+    level: String,
+    preset: Preset,
+    splits: Vec<u32>,
+}
+
+// Personal best splits, one `level difficulty tick tick ...` line per level and difficulty
+pub struct BestSplits {
+    entries: Vec<SplitEntry>,
+}
+
+impl BestSplits {
+    pub fn load() -> Self {
+        let text = std::fs::read_to_string(SPLITS_PATH).unwrap_or_default();
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let level = parts.next()?.to_string();
+                let preset = Preset::from_name(parts.next()?)?;
+                let splits = parts.map(|tick| tick.parse().ok()).collect::<Option<Vec<u32>>>()?;
+                Some(SplitEntry { level, preset, splits })
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    fn save(&self) {
+        let text: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let ticks: Vec<String> = entry.splits.iter().map(|tick| tick.to_string()).collect();
+                format!("{} {} {}\n", entry.level, entry.preset.name(), ticks.join(" "))
+            })
+            .collect();
+
+        // Not being able to save (e.g. on the web) shouldn't stop the game
+        let _ = std::fs::write(SPLITS_PATH, text);
+    }
+
+    pub fn best(&self, level: &str, preset: Preset) -> Option<Vec<u32>> {
+        self.entries
+            .iter()
+            .find(|entry| entry.level == level && entry.preset == preset)
+            .map(|entry| entry.splits.clone())
+    }
+
+    // Keeps the splits if the final time beats the personal best, returns true if it did
+    pub fn submit(&mut self, level: &str, preset: Preset, splits: &[u32]) -> bool {
+        let final_time = |splits: &[u32]| splits.last().copied().unwrap_or(u32::MAX);
+        match self.entries.iter_mut().find(|entry| entry.level == level && entry.preset == preset) {
+            // A level edited to have a different number of splits starts over
+            Some(entry) if entry.splits.len() == splits.len() && final_time(&entry.splits) <= final_time(splits) => {
+                return false;
+            }
+            Some(entry) => entry.splits = splits.to_vec(),
+            None => self.entries.push(SplitEntry { level: level.to_string(), preset, splits: splits.to_vec() }),
+        }
+        self.save();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_ticks() {
+        assert_eq!(format_ticks(0), "0:00.00");
+        assert_eq!(format_ticks(30), "0:00.50");
+        assert_eq!(format_ticks(TICK_RATE), "0:01.00");
+        assert_eq!(format_ticks(3741), "1:02.35");
+        assert_eq!(format_ticks(TICK_RATE * 3600), "60:00.00");
+    }
+
+    #[test]
+    fn formats_deltas() {
+        assert_eq!(format_delta(0), "+0.00");
+        assert_eq!(format_delta(31), "+0.51");
+        assert_eq!(format_delta(-72), "-1.20");
+    }
+}
@@ -0,0 +1,211 @@
+use macroquad::prelude::*;
+
+use crate::config::{ConfigFile, PhysicsConfig, Preset};
+use crate::layout::{JumpReach, Layout};
+use crate::level::{Level, LEVELS_FOLDER};
+use crate::mode::Abilities;
+use crate::{cactus_hitbox, PLAYER_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// Checks a level against the jump physics: everything in it has to be reachable from the
+// ground, and nothing may leave the player without a way out
+
+pub fn validate_level(level: &Level, physics: &PhysicsConfig, abilities: Abilities) -> Vec<String> {
+    let reach = JumpReach::new(physics, abilities);
+    let layout = level.layout.clone().unwrap_or_else(Layout::classic);
+    let mut problems = layout.problems(&reach);
+
+    // Coins and exits only have to be touched, from any platform the player can get to
+    let reached: Vec<Rect> = layout
+        .platforms
+        .iter()
+        .zip(layout.reachable(&reach))
+        .filter(|(_, reached)| *reached)
+        .map(|(platform, _)| platform.area())
+        .collect();
+    let touchable = |zone: &Rect| reached.iter().any(|from| reach.can_touch(*from, *zone));
+    for zone in level.coin_zones.iter().filter(|zone| !touchable(zone)) {
+        problems.push(format!("coin zone at ({:.0}, {:.0}) can't be reached", zone.x, zone.y));
+    }
+    for exit in level.exits.iter().filter(|exit| !touchable(exit)) {
+        problems.push(format!("exit at ({:.0}, {:.0}) can't be reached", exit.x, exit.y));
+    }
+
+    // Somewhere on every platform with cacti has to be wide enough to land on
+    for platform in &layout.platforms {
+        let mut hurts: Vec<Rect> = platform.cacti.iter().map(|&(x_offset, size)| cactus_hitbox(platform.pos, x_offset, size)).collect();
+        hurts.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+        let mut widest = 0.0_f32;
+        let mut free_from = platform.pos.x;
+        for hurt in &hurts {
+            widest = widest.max(hurt.left() - free_from);
+            free_from = free_from.max(hurt.right());
+        }
+        widest = widest.max(platform.pos.x + platform.size.x - free_from);
+        if widest < PLAYER_SIZE.x {
+            problems.push(format!("cacti leave no room to land on the platform at ({:.0}, {:.0})", platform.pos.x, platform.pos.y));
+        }
+    }
+
+    // A moving platform that comes closer than a player's width to another solid, at a height
+    // the player could be caught between the two, crushes them. The screen edges count too
+    let mut solids: Vec<(String, Rect)> = layout
+        .platforms
+        .iter()
+        .map(|platform| (format!("the platform at ({:.0}, {:.0})", platform.pos.x, platform.pos.y), platform.area()))
+        .collect();
+    solids.push(("the left edge of the screen".to_string(), Rect::new(-PLAYER_SIZE.x, 0.0, PLAYER_SIZE.x, WINDOW_HEIGHT)));
+    solids.push(("the right edge of the screen".to_string(), Rect::new(WINDOW_WIDTH, 0.0, PLAYER_SIZE.x, WINDOW_HEIGHT)));
+
+    for (i, mover) in layout.platforms.iter().enumerate().filter(|(_, platform)| platform.speed != 0.0) {
+        let sweep = mover.area();
+        for (j, (name, solid)) in solids.iter().enumerate() {
+            let caught_height = solid.top() < sweep.bottom() + PLAYER_SIZE.y && sweep.top() - PLAYER_SIZE.y < solid.bottom();
+            let gap = (solid.left() - sweep.right()).max(sweep.left() - solid.right());
+            if i != j && caught_height && gap < PLAYER_SIZE.x {
+                problems.push(format!("the moving platform at ({:.0}, {:.0}) can crush the player against {}", mover.pos.x, mover.pos.y, name));
+            }
+        }
+    }
+
+    problems
+}
+
+// `--check-levels`: validates every level file for every difficulty preset, returns false if anything failed
+pub fn check_levels() -> bool {
+    let entries = match std::fs::read_dir(LEVELS_FOLDER) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("couldn't read {}: {}", LEVELS_FOLDER, e);
+            return false;
+        }
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    paths.sort();
+
+    let config_file = ConfigFile::new();
+    let mut all_fine = true;
+    for path in paths {
+        let id = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                all_fine = false;
+                continue;
+            }
+        };
+
+        let (level, mut problems) = Level::parse(&id, &text);
+
+        // Physics differ per preset, note which ones a problem shows up with unless it's all of them
+        let mut found: Vec<(String, Vec<&str>)> = Vec::new();
+        for preset in Preset::ALL {
+            let physics = config_file.load(preset).physics;
+            // The modes that play levels allow every move
+            for problem in validate_level(&level, &physics, Abilities::default()) {
+                match found.iter_mut().find(|(known, _)| *known == problem) {
+                    Some((_, presets)) => presets.push(preset.name()),
+                    None => found.push((problem, vec![preset.name()])),
+                }
+            }
+        }
+        for (problem, presets) in found {
+            if presets.len() == Preset::ALL.len() {
+                problems.push(problem);
+            } else {
+                problems.push(format!("{} ({})", problem, presets.join(", ")));
+            }
+        }
+
+        if problems.is_empty() {
+            println!("{}: ok", path.display());
+        } else {
+            all_fine = false;
+            for problem in problems {
+                println!("{}: {}", path.display(), problem);
+            }
+        }
+    }
+    all_fine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(layout: &str) -> Vec<String> {
+        let (level, errors) = Level::parse("test", &format!("[layout]\n{}", layout));
+        assert!(errors.is_empty(), "{:?}", errors);
+        validate_level(&level, &PhysicsConfig::default(), Abilities::default())
+    }
+
+    fn reports(problems: &[String], text: &str) -> bool {
+        problems.iter().any(|problem| problem.contains(text))
+    }
+
+    #[test]
+    fn desert_is_fine() {
+        let level = Level::from_text("desert", include_str!("../assets/levels/desert.txt"));
+        let problems = validate_level(&level, &PhysicsConfig::default(), Abilities::default());
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+
+    #[test]
+    fn classic_is_fine() {
+        let (level, _) = Level::parse("classic", "");
+        let problems = validate_level(&level, &PhysicsConfig::default(), Abilities::default());
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+
+    #[test]
+    fn shadow_offset_is_capped() {
+        let (level, errors) = Level::parse("test", "[sun]\nmax_offset = 40");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(level.sun.max_offset, crate::level::SunConfig::default().max_offset);
+    }
+
+    #[test]
+    fn unreachable_platform() {
+        let problems = problems("platform = 0 585 800\nplatform = 300 100 200");
+        assert!(reports(&problems, "platform at (300, 100) can't be reached"), "{:?}", problems);
+    }
+
+    #[test]
+    fn platform_overhead_blocks_the_jump() {
+        // The top platform is only in reach from the middle one, right under it
+        let blocked = problems("platform = 0 585 800\nplatform = 300 400 200\nplatform = 300 230 200");
+        assert!(reports(&blocked, "platform at (300, 230) can't be reached"), "{:?}", blocked);
+
+        // Narrower, it leaves room to jump past its edges
+        let clear = problems("platform = 0 585 800\nplatform = 300 400 200\nplatform = 350 230 100");
+        assert!(!reports(&clear, "can't be reached"), "{:?}", clear);
+    }
+
+    #[test]
+    fn crushing_mover() {
+        let problems = problems("platform = 0 585 800\nmoving = 100 485 200 100 400\nplatform = 620 485 100");
+        assert!(reports(&problems, "moving platform at (100, 485) can crush the player against the platform at (620, 485)"), "{:?}", problems);
+    }
+
+    #[test]
+    fn mover_against_the_screen_edge() {
+        let problems = problems("platform = 0 585 800\nmoving = 100 485 200 20 300");
+        assert!(reports(&problems, "against the left edge of the screen"), "{:?}", problems);
+    }
+
+    #[test]
+    fn cacti_with_no_landing_room() {
+        let layout = "platform = 0 585 800\nplatform = 300 485 100\ncactus = 300 485 60\ncactus = 340 485 60";
+        assert!(reports(&problems(layout), "cacti leave no room to land on the platform at (300, 485)"));
+    }
+
+    #[test]
+    fn cacti_with_room_to_spare() {
+        let layout = "platform = 0 585 800\nplatform = 300 485 200\ncactus = 300 485 40";
+        assert!(!reports(&problems(layout), "no room to land"));
+    }
+}